@@ -0,0 +1,75 @@
+// Сравнение выборки через индексы с полным перебором списка:
+// cargo bench --bench select
+use chrono::{Duration as Hours, NaiveDate};
+use std::time::{Duration, Instant};
+use todo_list::list::{
+    query::SelectQuery,
+    task::{Priority, Task},
+    List,
+};
+
+const TASKS: usize = 50_000;
+const RUNS: u32 = 20;
+
+fn list() -> List {
+    let categories = ["Работа", "Дом", "Личное", "Учеба", "Здоровье"];
+    let start = NaiveDate::from_ymd_opt(2020, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    (0..TASKS)
+        .map(|i| Task {
+            id: i as u64 + 1,
+            title: format!("Задача {i}"),
+            descr: Some(format!("Описание {i}")),
+            date: Some((start + Hours::hours(i as i64)).into()),
+            category: Some(categories[i % categories.len()].into()),
+            tags: Vec::new(),
+            status: (i % 7 == 0).into(),
+            priority: Priority::ALL[i % Priority::ALL.len()],
+            parent: 0,
+            depends_on: Vec::new(),
+            repeat: Default::default(),
+        })
+        .collect::<Vec<_>>()
+        .iter()
+        .collect()
+}
+
+fn measure(mut f: impl FnMut() -> usize) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut count = 0;
+    for _ in 0..RUNS {
+        count = f();
+    }
+    (start.elapsed() / RUNS, count)
+}
+
+fn main() {
+    let list = list();
+    let queries = [
+        "select * where title = 'Задача 4242'",
+        "select * where category = 'Дом' and is_done = true",
+        "select * where date between '2023-03-01 00:00' and '2023-03-08 00:00'",
+        "select * where title like 'Задача 1%'",
+    ];
+
+    println!("Задач: {TASKS}");
+    for sql in queries {
+        let query = sql.parse::<SelectQuery>().unwrap();
+        let condition = query.condition.as_ref().unwrap();
+
+        // Без разбора запроса: только выборка задач
+        let (indexed, found) = measure(|| query.tasks(&list).len());
+        let (scan, scanned) = measure(|| {
+            list.iter()
+                .filter(|task| condition.is_match(task))
+                .cloned()
+                .collect::<Vec<_>>()
+                .len()
+        });
+        assert_eq!(found, scanned);
+
+        println!("{sql}\n  найдено: {found}, индексы: {indexed:?}, перебор: {scan:?}");
+    }
+}
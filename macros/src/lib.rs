@@ -2,7 +2,10 @@ use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
 use syn::{parse_macro_input, Fields, FieldsNamed, ItemStruct};
 
-#[proc_macro_derive(Table)]
+// Поля с атрибутом #[table(string)] - типы, которые serde сохраняет строкой
+// (перечисления, правила); их тип берется из имени типа.
+// Option<T> от доступного типа - необязательное поле, serde сохраняет его как null
+#[proc_macro_derive(Table, attributes(table))]
 pub fn table(input: TokenStream) -> TokenStream {
     let ItemStruct { ident, fields, .. } = parse_macro_input!(input);
 
@@ -10,17 +13,34 @@ pub fn table(input: TokenStream) -> TokenStream {
         panic!()
     };
 
-    let idents = named
+    let (idents, types): (Vec<_>, Vec<_>) = named
         .into_iter()
-        .map(|syn::Field { ident, ty, .. }| {
-            let avail_types = &["String", "Date", "bool"];
-            avail_types
-                .iter()
-                .find(|&&type_| type_ == ty.to_token_stream().to_string())
-                .expect(&format!("available types: {}", avail_types.join(", ")));
-            ident
-        })
-        .collect::<Vec<_>>();
+        .map(
+            |syn::Field {
+                 ident, ty, attrs, ..
+             }| {
+                let type_ = ty.to_token_stream().to_string().replace(' ', "");
+                let is_string = attrs.iter().any(|attr| {
+                    attr.path().is_ident("table")
+                        && attr
+                            .parse_args::<syn::Ident>()
+                            .is_ok_and(|ident| ident == "string")
+                });
+                let avail_types = &["u64", "String", "Vec<String>", "Vec<u64>", "Date", "bool"];
+                let inner = type_
+                    .strip_prefix("Option<")
+                    .and_then(|inner| inner.strip_suffix('>'))
+                    .unwrap_or(&type_);
+                if !is_string && !avail_types.contains(&inner) {
+                    panic!(
+                        "available types: {}, Option of them, or #[table(string)]",
+                        avail_types.join(", ")
+                    )
+                }
+                (ident, type_)
+            },
+        )
+        .unzip();
     let len = idents.len();
 
     quote! {
@@ -32,6 +52,9 @@ pub fn table(input: TokenStream) -> TokenStream {
             fn get_keys() -> [Self::Key; #len] {
                 [#(Self::Key::from(stringify!(#idents))),*]
             }
+            fn get_types() -> [&'static str; #len] {
+                [#(#types),*]
+            }
             fn get_values(&self) -> [Self::Value; #len] {
                 let serialized_task = serde_json::to_value(self).unwrap();
                 Task::get_keys()
@@ -0,0 +1,46 @@
+use chrono::{NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use std::{cell::Cell, env, sync::OnceLock};
+
+thread_local! {
+    static NOW: Cell<Option<NaiveDateTime>> = const { Cell::new(None) };
+    // В тестах по умолчанию UTC, чтобы результат не зависел от машины
+    static ZONE: Cell<Option<Tz>> = const { Cell::new(if cfg!(test) { Some(Tz::UTC) } else { None }) };
+}
+
+// Текущее локальное время, в тестах подменяется через with_now
+pub fn now() -> NaiveDateTime {
+    NOW.with(Cell::get)
+        .unwrap_or_else(|| Utc::now().with_timezone(&zone()).naive_local())
+}
+
+// Выполняет f с зафиксированным текущим временем
+pub fn with_now<T>(now: NaiveDateTime, f: impl FnOnce() -> T) -> T {
+    let previous = NOW.with(|cell| cell.replace(Some(now)));
+    let result = f();
+    NOW.with(|cell| cell.set(previous));
+    result
+}
+
+// Часовой пояс пользователя: переменная TODO_TZ (например, Europe/Moscow),
+// иначе пояс системы, иначе UTC
+pub fn zone() -> Tz {
+    static CONFIGURED: OnceLock<Tz> = OnceLock::new();
+    ZONE.with(Cell::get).unwrap_or_else(|| {
+        *CONFIGURED.get_or_init(|| {
+            env::var("TODO_TZ")
+                .ok()
+                .or_else(|| iana_time_zone::get_timezone().ok())
+                .and_then(|name| name.parse().ok())
+                .unwrap_or(Tz::UTC)
+        })
+    })
+}
+
+// Выполняет f в заданном часовом поясе пользователя
+pub fn with_zone<T>(zone: Tz, f: impl FnOnce() -> T) -> T {
+    let previous = ZONE.with(|cell| cell.replace(Some(zone)));
+    let result = f();
+    ZONE.with(|cell| cell.set(previous));
+    result
+}
@@ -0,0 +1,333 @@
+use super::*;
+use condition::{bind_value, parse_value, Condition, Operand};
+use dialect::TaskDialect;
+use explain::Explain;
+use prepared::Prepared;
+use query::SelectQuery;
+use rows::Rows;
+use sqlparser::{
+    ast::{
+        Assignment, AssignmentTarget, Delete, FromTable, Insert, ObjectName, TableFactor,
+        TableWithJoins,
+    },
+    keywords::Keyword,
+};
+use std::{collections::HashSet, str::FromStr};
+
+// Разобранная SQL команда
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Select(SelectQuery),
+    Explain(SelectQuery),
+    // Строки VALUES: значения по полям, остальные поля по умолчанию
+    Insert(Vec<Vec<(String, Operand)>>),
+    Update {
+        assignments: Vec<(String, Operand)>,
+        condition: Option<Condition>,
+    },
+    Delete {
+        condition: Option<Condition>,
+    },
+}
+
+// Результат выполнения команды
+#[derive(Clone, Debug, PartialEq)]
+pub enum Output {
+    Rows(Rows),
+    Explain(Explain),
+    Inserted(usize),
+    Updated(usize),
+    Deleted(usize),
+}
+
+impl Display for Output {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Output::Rows(rows) => write!(f, "{rows}"),
+            Output::Explain(explain) => write!(f, "{explain}"),
+            Output::Inserted(count) => write!(f, "Добавлено задач: {count}"),
+            Output::Updated(count) => write!(f, "Обновлено задач: {count}"),
+            Output::Deleted(count) => write!(f, "Удалено задач: {count}"),
+        }
+    }
+}
+
+// Ровно одно выражение sql и его токены для мест ошибок
+pub fn parse_statement(sql: &str) -> Result<(Statement, Locator), ListError> {
+    let mut ast = Parser::parse_sql(&TaskDialect::default(), sql)
+        .map_err(|e| Sql(diagnostic::syntax_error(sql, e)))?;
+
+    if ast.len() == 1 {
+        Ok((ast.remove(0), Locator::new(sql)))
+    } else {
+        Err(Sql(NotValidQuery))
+    }
+}
+
+fn check_table_name(name: &ObjectName) -> Result<(), ListError> {
+    if name.to_string().eq_ignore_ascii_case("tasks") {
+        Ok(())
+    } else {
+        Err(Sql(NonExistentTable(name.to_string().into())))
+    }
+}
+
+// Единственная таблица - tasks
+pub fn check_table(table: &TableWithJoins) -> Result<(), ListError> {
+    match table {
+        TableWithJoins {
+            relation: TableFactor::Table { name, .. },
+            joins,
+        } if joins.is_empty() => check_table_name(name),
+        _ => Err(Sql(NotValidQuery)),
+    }
+}
+
+// id выдается списком и не меняется запросами, виртуальные столбцы считаются по списку
+fn check_writable(field: String) -> Result<String, ListError> {
+    if field == "id" || depends::VIRTUAL_KEYS.contains(&field.as_str()) {
+        Err(Sql(ReadOnlyField(field.into())))
+    } else {
+        Ok(field)
+    }
+}
+
+fn parse_assignment(
+    Assignment { target, value }: Assignment,
+) -> Result<(String, Operand), ListError> {
+    let AssignmentTarget::ColumnName(name) = target else {
+        return Err(Sql(NotValidQuery));
+    };
+    let field = check_writable(name.to_string())?;
+    let value = assigned_value(&field, value)?;
+    Ok((field, value))
+}
+
+// Значение для SET и VALUES: NULL можно записать только в необязательное поле
+fn assigned_value(field: &str, expr: Expr) -> Result<Operand, ListError> {
+    match expr {
+        Expr::Value(ast::Value::Null) if Task::is_optional(field) => Ok(Operand::Null),
+        expr => parse_value(field, expr),
+    }
+}
+
+// Запрос без параметров
+impl FromStr for Command {
+    type Err = ListError;
+    fn from_str(sql: &str) -> Result<Self, Self::Err> {
+        sql.parse::<Prepared>()?.bind(&[])
+    }
+}
+
+impl Command {
+    pub fn from_statement(statement: Statement, locator: &mut Locator) -> Result<Self, ListError> {
+        match statement {
+            Statement::Query(query) => Ok(Self::Select(SelectQuery::from_query(*query, locator)?)),
+            Statement::Explain { statement, .. } => match *statement {
+                Statement::Query(query) => {
+                    Ok(Self::Explain(SelectQuery::from_query(*query, locator)?))
+                }
+                _ => Err(Sql(NotValidQuery)),
+            },
+            Statement::Insert(Insert {
+                table_name,
+                columns,
+                source: Some(source),
+                ..
+            }) => {
+                check_table_name(&table_name).map_err(|e| locator.locate(e))?;
+
+                // Без списка столбцов значения идут в порядке ключей Table, кроме id
+                let fields = if columns.is_empty() {
+                    Task::editable_keys()
+                } else {
+                    columns
+                        .into_iter()
+                        .map(|ident| locator.parse(ident, |ident| check_writable(ident.value)))
+                        .collect::<Result<Vec<_>, _>>()?
+                };
+                if !fields.iter().any(|field| field == "title") {
+                    return Err(Sql(MissingField("title".into())));
+                }
+
+                let SetExpr::Values(values) = *source.body else {
+                    return Err(Sql(NotValidQuery));
+                };
+                locator.clause(Keyword::VALUES);
+                values
+                    .rows
+                    .into_iter()
+                    .map(|row| {
+                        if row.len() != fields.len() {
+                            return Err(locator.locate(Sql(SqlError::format(
+                                format!("VALUES ({})", fields.join(", ")),
+                                format!("({})", ast::display_comma_separated(&row)),
+                            ))));
+                        }
+                        fields
+                            .iter()
+                            .zip(row)
+                            .map(|(field, expr)| {
+                                let value =
+                                    locator.parse(expr, |expr| assigned_value(field, expr))?;
+                                Ok((field.clone(), value))
+                            })
+                            .collect()
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Self::Insert)
+            }
+            Statement::Update {
+                table,
+                assignments,
+                from: None,
+                selection,
+                returning: None,
+            } => {
+                check_table(&table).map_err(|e| locator.locate(e))?;
+                locator.clause(Keyword::SET);
+                let assignments = assignments
+                    .into_iter()
+                    .map(|assignment| locator.parse(assignment, parse_assignment))
+                    .collect::<Result<Vec<_>, _>>()?;
+                locator.clause(Keyword::WHERE);
+                Ok(Self::Update {
+                    assignments,
+                    condition: selection
+                        .map(|expr| Condition::parse(expr, locator))
+                        .transpose()?,
+                })
+            }
+            Statement::Delete(Delete {
+                tables,
+                from: FromTable::WithFromKeyword(from),
+                using: None,
+                selection,
+                returning: None,
+                order_by,
+                limit: None,
+            }) if tables.is_empty() && order_by.is_empty() => {
+                match from.as_slice() {
+                    [table] => check_table(table).map_err(|e| locator.locate(e))?,
+                    _ => return Err(Sql(NotValidQuery)),
+                }
+                locator.clause(Keyword::WHERE);
+                Ok(Self::Delete {
+                    condition: selection
+                        .map(|expr| Condition::parse(expr, locator))
+                        .transpose()?,
+                })
+            }
+            _ => Err(Sql(NotValidQuery)),
+        }
+    }
+
+    // Команда с подставленными значениями параметров
+    pub fn bind(&self, params: &[Operand]) -> Result<Self, ListError> {
+        let bind_fields = |fields: &[(String, Operand)]| {
+            fields
+                .iter()
+                .map(|(field, value)| Ok((field.clone(), bind_value(field, None, value, params)?)))
+                .collect::<Result<Vec<_>, ListError>>()
+        };
+        let bind_condition = |condition: &Option<Condition>| {
+            condition
+                .as_ref()
+                .map(|condition| condition.bind(params))
+                .transpose()
+        };
+
+        Ok(match self {
+            Command::Select(query) => Command::Select(query.bind(params)?),
+            Command::Explain(query) => Command::Explain(query.bind(params)?),
+            Command::Insert(rows) => Command::Insert(
+                rows.iter()
+                    .map(|row| bind_fields(row))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            Command::Update {
+                assignments,
+                condition,
+            } => Command::Update {
+                assignments: bind_fields(assignments)?,
+                condition: bind_condition(condition)?,
+            },
+            Command::Delete { condition } => Command::Delete {
+                condition: bind_condition(condition)?,
+            },
+        })
+    }
+
+    pub fn execute(self, list: &mut List) -> Result<Output, ListError> {
+        let is_match = |condition: &Option<Condition>, task: &Task| {
+            condition.as_ref().is_none_or(|c| c.is_match(task))
+        };
+
+        match self {
+            Command::Select(query) => Ok(Output::Rows(query.rows(list))),
+            Command::Explain(query) => Ok(Output::Explain(query.explain(list))),
+            Command::Insert(rows) => {
+                // Добавляем все строки или ни одной
+                let mut new_list = list.clone();
+                let count = rows.len();
+                for row in rows {
+                    let mut task = Task::default();
+                    for (field, value) in row {
+                        value.set_to_task(&mut task, &field);
+                    }
+                    new_list.add(task)?;
+                }
+                *list = new_list;
+                Ok(Output::Inserted(count))
+            }
+            Command::Update {
+                assignments,
+                condition,
+            } => {
+                let condition = condition.map(|c| c.resolve(list));
+                let positions = (0..list.len())
+                    .filter(|pos| is_match(&condition, &list[*pos]))
+                    .collect::<Vec<_>>();
+                // Родители проверяются после всех изменений, при ошибке список не меняется
+                let mut new_list = list.clone();
+                for pos in &positions {
+                    let mut task = new_list[*pos].clone();
+                    for (field, value) in &assignments {
+                        value.clone().set_to_task(&mut task, field);
+                    }
+                    new_list.replace(*pos, task);
+                }
+                for pos in &positions {
+                    let task = &new_list[*pos];
+                    new_list.check_parent(task)?;
+                    new_list.check_dependencies(task)?;
+                    // Статус меняется по тем же правилам, что и в set_status
+                    if list[*pos].status != task.status {
+                        new_list.check_transition(list[*pos].status, task)?;
+                    }
+                }
+                // Выполненные повторяющиеся задачи порождают следующие, как в done
+                for pos in &positions {
+                    if list[*pos].status != Status::Done && new_list[*pos].status == Status::Done {
+                        let task = new_list.complete(new_list[*pos].clone());
+                        new_list.replace(*pos, task);
+                    }
+                }
+                *list = new_list;
+                Ok(Output::Updated(positions.len()))
+            }
+            Command::Delete { condition } => {
+                // Вместе с задачами удаляются их подзадачи
+                let condition = condition.map(|c| c.resolve(list));
+                let deleted = list
+                    .iter()
+                    .filter(|task| is_match(&condition, task))
+                    .flat_map(|task| list.subtree(task.id))
+                    .collect::<HashSet<_>>();
+                let len = list.len();
+                list.retain(|task| !is_match(&condition, task) && !deleted.contains(&task.id));
+                Ok(Output::Deleted(len - list.len()))
+            }
+        }
+    }
+}
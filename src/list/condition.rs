@@ -0,0 +1,864 @@
+use super::*;
+use datetime::DateFunction;
+use pattern::Pattern;
+use sqlparser::ast::UnaryOperator;
+use std::cmp::Ordering;
+
+// Скомпилированное условие WHERE
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+    Compare {
+        field: String,
+        function: Option<DateFunction>,
+        op: BinaryOperator,
+        value: Operand,
+    },
+    Match {
+        field: String,
+        pattern: Pattern,
+    },
+    In {
+        field: String,
+        function: Option<DateFunction>,
+        values: Vec<Operand>,
+    },
+    // 'x' = ANY(tags): список содержит значение
+    Contains {
+        field: String,
+        value: Operand,
+    },
+    IsNull(String),
+    // SUBTREE(id): задача и все ее подзадачи, заменяется на id IN (..) в resolve
+    Subtree(Operand),
+}
+
+// Значение поля задачи, с которым сравнивается правая часть условия
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Operand {
+    String(String),
+    Date(Date),
+    Bool(bool),
+    Number(i64),
+    Priority(Priority),
+    Status(Status),
+    Array(Vec<String>),
+    Ids(Vec<u64>),
+    // Незаданное необязательное поле: не равно и не сравнимо ни с чем, см. IS NULL
+    Null,
+    // Номер параметра подготовленного запроса с нуля, заменяется значением в bind
+    Param(usize),
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::String(value) => write!(f, "'{}'", value.replace('\'', "''")),
+            Operand::Date(value) => write!(f, "'{value}'"),
+            Operand::Bool(value) => write!(f, "{value}"),
+            Operand::Number(value) => write!(f, "{value}"),
+            Operand::Priority(value) => write!(f, "'{value}'"),
+            Operand::Status(value) => write!(f, "'{value}'"),
+            Operand::Array(values) => write!(
+                f,
+                "ARRAY[{}]",
+                values
+                    .iter()
+                    .map(|value| Operand::from(value.as_str()).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Operand::Ids(ids) => write!(
+                f,
+                "ARRAY[{}]",
+                ids.iter()
+                    .map(u64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Operand::Null => write!(f, "NULL"),
+            Operand::Param(number) => write!(f, "${}", number + 1),
+        }
+    }
+}
+
+impl From<&str> for Operand {
+    fn from(value: &str) -> Self {
+        Operand::String(value.into())
+    }
+}
+
+impl From<String> for Operand {
+    fn from(value: String) -> Self {
+        Operand::String(value)
+    }
+}
+
+impl Operand {
+    // Сравнение в условиях: даты на весь день сравниваются как промежуток, см. Date::compare
+    pub fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Operand::Null, _) | (_, Operand::Null) => None,
+            (Operand::Date(lhs), Operand::Date(rhs)) => Some(lhs.compare(rhs)),
+            _ => self.partial_cmp(other),
+        }
+    }
+}
+
+impl From<Date> for Operand {
+    fn from(value: Date) -> Self {
+        Operand::Date(value)
+    }
+}
+
+impl From<bool> for Operand {
+    fn from(value: bool) -> Self {
+        Operand::Bool(value)
+    }
+}
+
+impl From<i64> for Operand {
+    fn from(value: i64) -> Self {
+        Operand::Number(value)
+    }
+}
+
+impl From<Priority> for Operand {
+    fn from(value: Priority) -> Self {
+        Operand::Priority(value)
+    }
+}
+
+impl From<Status> for Operand {
+    fn from(value: Status) -> Self {
+        Operand::Status(value)
+    }
+}
+
+impl Operand {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Operand::String(_) => "String",
+            Operand::Date(_) => "Date",
+            Operand::Bool(_) => "Bool",
+            Operand::Number(_) => "Number",
+            Operand::Priority(_) => "Priority",
+            Operand::Status(_) => "Status",
+            Operand::Array(_) => "Array",
+            Operand::Ids(_) => "Ids",
+            Operand::Null => "Null",
+            Operand::Param(_) => "Param",
+        }
+    }
+
+    pub fn from_task(task: &Task, field: &str) -> Option<Self> {
+        Some(match field {
+            "id" => Operand::Number(task.id as i64),
+            "title" => Operand::String(task.title.clone()),
+            "descr" => task.descr.clone().map_or(Operand::Null, Operand::String),
+            "date" => task.date.clone().map_or(Operand::Null, Operand::Date),
+            "category" => task.category.clone().map_or(Operand::Null, Operand::String),
+            "tags" => Operand::Array(task.tags.clone()),
+            "status" => Operand::Status(task.status),
+            "is_done" => Operand::Bool(task.status == Status::Done),
+            "priority" => Operand::Priority(task.priority),
+            "parent" => Operand::Number(task.parent as i64),
+            "depends_on" => Operand::Ids(task.depends_on.clone()),
+            "repeat" => Operand::String(task.repeat.to_string()),
+            _ => return None,
+        })
+    }
+
+    // Значение поля с учетом функции от даты в левой части
+    pub fn from_task_with(
+        task: &Task,
+        field: &str,
+        function: Option<DateFunction>,
+    ) -> Option<Self> {
+        match (Self::from_task(task, field)?, function) {
+            (Operand::Date(date), Some(function)) => Some(function.apply(&date)),
+            (operand, _) => Some(operand),
+        }
+    }
+
+    // Записывает значение в поле задачи, тип уже проверен в parse_value,
+    // id только для чтения
+    pub fn set_to_task(self, task: &mut Task, field: &str) {
+        match (field, self) {
+            ("title", Operand::String(value)) => task.title = value,
+            ("descr", Operand::String(value)) => task.descr = Some(value),
+            ("date", Operand::Date(value)) => task.date = Some(value),
+            ("category", Operand::String(value)) => task.category = Some(value),
+            ("descr", Operand::Null) => task.descr = None,
+            ("date", Operand::Null) => task.date = None,
+            ("category", Operand::Null) => task.category = None,
+            ("tags", Operand::Array(value)) => task.tags = value,
+            ("status", Operand::Status(value)) => task.status = value,
+            ("is_done", Operand::Bool(true)) => task.status = Status::Done,
+            ("is_done", Operand::Bool(false)) if task.status == Status::Done => {
+                task.status = Status::Todo
+            }
+            ("is_done", Operand::Bool(false)) => {}
+            ("priority", Operand::Priority(value)) => task.priority = value,
+            ("parent", Operand::Number(value)) => task.parent = value as u64,
+            ("depends_on", Operand::Ids(value)) => task.depends_on = value,
+            ("repeat", Operand::String(value)) => task.repeat = value.parse().unwrap(),
+            _ => unreachable!(),
+        }
+    }
+}
+
+// Тип значения, ожидаемый полем с учетом функции от даты
+fn value_type(field: &str, function: Option<DateFunction>) -> &'static str {
+    match (field, function) {
+        ("date", None | Some(DateFunction::Date)) => "Date",
+        ("date", Some(_)) | ("id" | "parent", _) => "Number",
+        ("is_done" | "is_blocked", _) => "Bool",
+        ("priority", _) => "Priority",
+        ("status", _) => "Status",
+        ("tags", _) => "Array",
+        ("depends_on", _) => "Ids",
+        ("repeat", _) => "Recurrence",
+        _ => "String",
+    }
+}
+
+// Неотрицательное целое число
+fn number(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Value(ast::Value::Number(number, _)) => number.parse().ok(),
+        _ => None,
+    }
+    .filter(|number| *number >= 0)
+}
+
+// Параметр $n: при разборе в Prepared ? уже пронумерованы по порядку
+fn param(expr: &Expr) -> Option<Operand> {
+    match expr {
+        Expr::Value(ast::Value::Placeholder(placeholder)) => placeholder
+            .strip_prefix('$')
+            .and_then(|number| number.parse::<usize>().ok())
+            .filter(|number| *number > 0)
+            .map(|number| Operand::Param(number - 1)),
+        _ => None,
+    }
+}
+
+// Подставляет значение параметра с проверкой его типа
+pub fn bind_value(
+    field: &str,
+    function: Option<DateFunction>,
+    value: &Operand,
+    params: &[Operand],
+) -> Result<Operand, ListError> {
+    let Operand::Param(number) = value else {
+        return Ok(value.clone());
+    };
+    bind_typed(value_type(field, function), *number, params)
+}
+
+fn bind_typed(expected: &str, number: usize, params: &[Operand]) -> Result<Operand, ListError> {
+    let param = &params[number];
+    match param {
+        param if param.type_name() == expected => Ok(param.clone()),
+        // Приоритет и статус можно передать строкой
+        Operand::String(value) if expected == "Priority" && value.parse::<Priority>().is_ok() => {
+            Ok(Operand::Priority(value.parse().unwrap()))
+        }
+        Operand::String(value) if expected == "Status" && value.parse::<Status>().is_ok() => {
+            Ok(Operand::Status(value.parse().unwrap()))
+        }
+        // Правило повторения - строкой RRULE
+        Operand::String(value) if expected == "Recurrence" => value
+            .parse::<Recurrence>()
+            .map(|recurrence| Operand::String(recurrence.to_string()))
+            .map_err(|_| {
+                Sql(ParamType {
+                    number: number + 1,
+                    expected: expected.into(),
+                    found: param.type_name().into(),
+                })
+            }),
+        // Теги - строкой через запятую
+        Operand::String(value) if expected == "Array" => {
+            Ok(Operand::Array(Task::parse_tags(value)))
+        }
+        _ => Err(Sql(ParamType {
+            number: number + 1,
+            expected: expected.into(),
+            found: param.type_name().into(),
+        })),
+    }
+}
+
+fn identifier(expr: Expr) -> Result<String, ListError> {
+    if let Expr::Identifier(ident) = expr {
+        Ok(ident.value)
+    } else {
+        Err(Sql(SqlError::format("WHERE [Identifier ..]", expr)))
+    }
+}
+
+pub fn string_value(format: &str, expr: Expr) -> Result<String, ListError> {
+    match expr {
+        Expr::Value(ast::Value::SingleQuotedString(value)) => Ok(value),
+        Expr::Identifier(ident) if matches!(ident.quote_style, Some('"')) => Ok(ident.value),
+        expr => Err(Sql(SqlError::format(format, expr))),
+    }
+}
+
+fn check_field(field: String) -> Result<String, ListError> {
+    if Task::has_column(&field) || depends::VIRTUAL_KEYS.contains(&field.as_str()) {
+        Ok(field)
+    } else {
+        Err(Sql(NonExistentField(field.into())))
+    }
+}
+
+// Строки, bool и статус сравниваются только на равенство, дата, номера и приоритет - любым оператором,
+// списки только через IN field и CONTAINS
+fn check_operator(field: &str, op: &BinaryOperator) -> Result<(), ListError> {
+    match field {
+        "tags" | "depends_on" => Err(Sql(SqlError::format(
+            format!("[.. IN {field} | {field} CONTAINS ..]"),
+            format!("{field} {op}"),
+        ))),
+        "title" | "descr" | "category" | "repeat" | "status" | "is_done" | "is_blocked"
+            if *op != Eq =>
+        {
+            Err(Sql(SqlError::format(format!("{field} = .."), op)))
+        }
+        "id" | "parent" | "title" | "descr" | "category" | "repeat" | "status" | "is_done"
+        | "is_blocked" | "date" | "priority" => Ok(()),
+        _ => Err(Sql(NonExistentField(field.into()))),
+    }
+}
+
+// Значение правой части условия с проверкой типа поля
+pub fn parse_value(field: &str, expr: Expr) -> Result<Operand, ListError> {
+    if let Some(param) = param(&expr) {
+        return check_field(field.into()).map(|_| param);
+    }
+    match field {
+        "title" | "descr" | "category" => {
+            string_value("[.. StringValue]", expr).map(Operand::String)
+        }
+        "tags" => match expr {
+            Expr::Array(ast::Array { elem, .. }) => elem
+                .into_iter()
+                .map(|expr| string_value("ARRAY[StringValue, ..]", expr))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|tags| {
+                    let mut unique = Vec::new();
+                    Task::add_tags(&mut unique, tags.iter().map(String::as_str));
+                    Operand::Array(unique)
+                }),
+            expr => string_value("[.. ARRAY[StringValue, ..] | 'tag, ..']", expr)
+                .map(|tags| Operand::Array(Task::parse_tags(&tags))),
+        },
+        "date" => datetime::parse_date(expr).map(Operand::Date),
+        // Правило хранится в каноническом виде, чтобы сравнение не зависело от записи
+        "repeat" => {
+            let format = "[.. 'FREQ=..;..' | '']";
+            let value = string_value(format, expr.clone())?;
+            value
+                .parse::<Recurrence>()
+                .map(|recurrence| Operand::String(recurrence.to_string()))
+                .map_err(|_| Sql(SqlError::format(format, expr)))
+        }
+        "depends_on" => match expr {
+            Expr::Array(ast::Array { elem, .. }) => elem
+                .into_iter()
+                .map(|expr| {
+                    number(&expr)
+                        .map(|id| id as u64)
+                        .ok_or_else(|| Sql(SqlError::format("ARRAY[Number, ..]", expr)))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(|mut ids| {
+                    let mut seen = std::collections::HashSet::new();
+                    ids.retain(|id| seen.insert(*id));
+                    Operand::Ids(ids)
+                }),
+            expr => Err(Sql(SqlError::format("[.. ARRAY[Number, ..]]", expr))),
+        },
+        "id" | "parent" => number(&expr)
+            .map(Operand::Number)
+            .ok_or_else(|| Sql(SqlError::format("[.. Number]", expr))),
+        "is_done" | "is_blocked" => {
+            if let Expr::Value(ast::Value::Boolean(value)) = expr {
+                Ok(Operand::Bool(value))
+            } else {
+                Err(Sql(SqlError::format("[.. true | false]", expr)))
+            }
+        }
+        "priority" => string_value("[.. 'low' | 'medium' | 'high' | 'urgent']", expr.clone())
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .map(Operand::Priority)
+            .ok_or_else(|| {
+                Sql(SqlError::format(
+                    "[.. 'low' | 'medium' | 'high' | 'urgent']",
+                    expr,
+                ))
+            }),
+        "status" => {
+            let format = format!(
+                "[.. {}]",
+                Status::ALL.map(|status| format!("'{status}'")).join(" | ")
+            );
+            string_value(&format, expr.clone())
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .map(Operand::Status)
+                .ok_or_else(|| Sql(SqlError::format(format, expr)))
+        }
+        _ => Err(Sql(NonExistentField(field.into()))),
+    }
+}
+
+// Левая часть сравнения: поле, DATE(date) или EXTRACT(.. FROM date)
+fn parse_target(expr: Expr) -> Result<(String, Option<DateFunction>), ListError> {
+    let found = expr.to_string();
+    let function = match expr {
+        Expr::Function(function) => match datetime::function_argument(&function, "date") {
+            Some(expr) => (identifier(expr)?, DateFunction::Date),
+            None => return Err(Sql(UnhandledFunction(function.to_string().into()))),
+        },
+        Expr::Extract { field, expr, .. } => {
+            (identifier(*expr)?, DateFunction::from_extract(&field)?)
+        }
+        expr => return Ok((identifier(expr)?, None)),
+    };
+
+    match function {
+        (field, function) if field == "date" => Ok((field, Some(function))),
+        _ => Err(Sql(SqlError::format("[DATE | EXTRACT](date)", found))),
+    }
+}
+
+// Значение правой части с учетом функции от даты в левой части
+fn parse_target_value(
+    field: &str,
+    function: Option<DateFunction>,
+    expr: Expr,
+) -> Result<Operand, ListError> {
+    match function {
+        None | Some(DateFunction::Date) => parse_value(field, expr),
+        Some(_) => match &expr {
+            Expr::Value(ast::Value::Number(number, _)) => number.parse().ok().map(Operand::Number),
+            expr => param(expr),
+        }
+        .ok_or_else(|| Sql(SqlError::format("[.. Number]", expr))),
+    }
+}
+
+impl Condition {
+    // Условия AND, OR и NOT разбираются рекурсивно, остальные - как отдельные элементы запроса
+    pub fn parse(expr: Expr, locator: &mut Locator) -> Result<Self, ListError> {
+        match expr {
+            Expr::Nested(expr) => Self::parse(*expr, locator),
+            Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr,
+            } => Ok(Self::Not(Box::new(Self::parse(*expr, locator)?))),
+            Expr::BinaryOp {
+                left,
+                op: And,
+                right,
+            } => Ok(Self::And(
+                Box::new(Self::parse(*left, locator)?),
+                Box::new(Self::parse(*right, locator)?),
+            )),
+            Expr::BinaryOp {
+                left,
+                op: Or,
+                right,
+            } => Ok(Self::Or(
+                Box::new(Self::parse(*left, locator)?),
+                Box::new(Self::parse(*right, locator)?),
+            )),
+            expr => locator.parse(expr, Self::parse_predicate),
+        }
+    }
+
+    fn parse_predicate(expr: Expr) -> Result<Self, ListError> {
+        match expr {
+            Expr::Like {
+                negated,
+                expr,
+                pattern,
+                escape_char,
+            } => Self::parse_match("like", *expr, *pattern, negated, |pattern| {
+                Pattern::like(&pattern, escape_char, false)
+            }),
+            Expr::ILike {
+                negated,
+                expr,
+                pattern,
+                escape_char,
+            } => Self::parse_match("ilike", *expr, *pattern, negated, |pattern| {
+                Pattern::like(&pattern, escape_char, true)
+            }),
+            Expr::RLike {
+                negated,
+                expr,
+                pattern,
+                ..
+            } => Self::parse_match("regexp", *expr, *pattern, negated, |pattern| {
+                Pattern::regex(&pattern, false)
+            }),
+            Expr::BinaryOp {
+                left,
+                op: op @ (PGRegexMatch | PGRegexIMatch | PGRegexNotMatch | PGRegexNotIMatch),
+                right,
+            } => {
+                let negated = matches!(op, PGRegexNotMatch | PGRegexNotIMatch);
+                let case_insensitive = matches!(op, PGRegexIMatch | PGRegexNotIMatch);
+                Self::parse_match(&op.to_string(), *left, *right, negated, |pattern| {
+                    Pattern::regex(&pattern, case_insensitive)
+                })
+            }
+            Expr::BinaryOp { left, op, right } => match op {
+                Eq | Gt | Lt | GtEq | LtEq => Self::parse_compare(*left, op, *right),
+                op => Err(Sql(UnhandledOperator(op.to_string().into()))),
+            },
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let (field, function) = parse_target(*expr)?;
+                let values = list
+                    .into_iter()
+                    .map(|expr| parse_target_value(&field, function, expr))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Self::In {
+                    field,
+                    function,
+                    values,
+                }
+                .negate(negated))
+            }
+            Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => {
+                let (field, function) = parse_target(*expr)?;
+                check_operator(&field, &GtEq)?;
+                let (low, high) = (
+                    parse_target_value(&field, function, *low)?,
+                    parse_target_value(&field, function, *high)?,
+                );
+                let compare = |op, value| Self::Compare {
+                    field: field.clone(),
+                    function,
+                    op,
+                    value,
+                };
+                Ok(
+                    Self::And(Box::new(compare(GtEq, low)), Box::new(compare(LtEq, high)))
+                        .negate(negated),
+                )
+            }
+            Expr::AnyOp {
+                left,
+                compare_op: Eq,
+                right,
+            } => {
+                let found = right.to_string();
+                let field = identifier(*right)
+                    .ok()
+                    .filter(|field| field == "tags" || field == "depends_on")
+                    .ok_or_else(|| Sql(SqlError::format("[.. IN tags | depends_on]", found)))?;
+                let value = match (param(&left), field.as_str()) {
+                    (Some(param), _) => param,
+                    (None, "tags") => {
+                        string_value("[StringValue IN tags]", *left).map(Operand::String)?
+                    }
+                    (None, _) => number(&left)
+                        .map(Operand::Number)
+                        .ok_or_else(|| Sql(SqlError::format("[Number IN depends_on]", left)))?,
+                };
+                Ok(Self::Contains { field, value })
+            }
+            Expr::IsNull(expr) => Ok(Self::IsNull(check_field(identifier(*expr)?)?)),
+            Expr::IsNotNull(expr) => Ok(Self::Not(Box::new(Self::IsNull(check_field(
+                identifier(*expr)?,
+            )?)))),
+            Expr::Function(function) => {
+                let found = function.to_string();
+                let root = datetime::function_argument(&function, "subtree")
+                    .ok_or(Sql(UnhandledFunction(found.into())))?;
+                param(&root)
+                    .or_else(|| number(&root).map(Operand::Number))
+                    .map(Self::Subtree)
+                    .ok_or_else(|| Sql(SqlError::format("SUBTREE(Number)", root)))
+            }
+            expr => Err(Sql(UnhandledCondition(expr.to_string().into()))),
+        }
+    }
+
+    // Условие, в котором SUBTREE и виртуальные столбцы заменены номерами подходящих задач
+    pub fn resolve(&self, list: &List) -> Self {
+        match self {
+            Self::And(lhs, rhs) => {
+                Self::And(Box::new(lhs.resolve(list)), Box::new(rhs.resolve(list)))
+            }
+            Self::Or(lhs, rhs) => {
+                Self::Or(Box::new(lhs.resolve(list)), Box::new(rhs.resolve(list)))
+            }
+            Self::Not(condition) => Self::Not(Box::new(condition.resolve(list))),
+            Self::Subtree(Operand::Number(root)) => Self::In {
+                field: "id".into(),
+                function: None,
+                values: list
+                    .subtree(*root as u64)
+                    .into_iter()
+                    .map(|id| Operand::Number(id as i64))
+                    .collect(),
+            },
+            condition @ (Self::Compare { field, .. } | Self::In { field, .. })
+                if depends::VIRTUAL_KEYS.contains(&field.as_str()) =>
+            {
+                Self::In {
+                    field: "id".into(),
+                    function: None,
+                    values: list
+                        .iter()
+                        .filter(|task| {
+                            condition.is_match_value(&list.virtual_value(task, field).unwrap())
+                        })
+                        .map(|task| Operand::Number(task.id as i64))
+                        .collect(),
+                }
+            }
+            condition => condition.clone(),
+        }
+    }
+
+    fn negate(self, negated: bool) -> Self {
+        if negated {
+            Self::Not(Box::new(self))
+        } else {
+            self
+        }
+    }
+
+    // Сопоставление строкового поля с шаблоном
+    fn parse_match(
+        op: &str,
+        expr: Expr,
+        pattern: Expr,
+        negated: bool,
+        build: impl FnOnce(String) -> Result<Pattern, ListError>,
+    ) -> Result<Self, ListError> {
+        let found = expr.to_string();
+        let field = identifier(expr)
+            .ok()
+            .filter(|field| ["title", "descr", "category"].contains(&field.as_str()))
+            .ok_or_else(|| {
+                Sql(SqlError::format(
+                    format!("[StringIdnetifier {op} ..]"),
+                    found,
+                ))
+            })?;
+        let pattern = build(string_value(&format!("[.. {op} StringValue]"), pattern)?)?;
+
+        Ok(Self::Match { field, pattern }.negate(negated))
+    }
+
+    fn parse_compare(left: Expr, op: BinaryOperator, right: Expr) -> Result<Self, ListError> {
+        let (field, function) = parse_target(left)?;
+        check_operator(&field, &op)?;
+        let value = parse_target_value(&field, function, right)?;
+        Ok(Self::Compare {
+            field,
+            function,
+            op,
+            value,
+        })
+    }
+
+    // Условие с подставленными значениями параметров
+    pub fn bind(&self, params: &[Operand]) -> Result<Self, ListError> {
+        Ok(match self {
+            Self::And(lhs, rhs) => {
+                Self::And(Box::new(lhs.bind(params)?), Box::new(rhs.bind(params)?))
+            }
+            Self::Or(lhs, rhs) => {
+                Self::Or(Box::new(lhs.bind(params)?), Box::new(rhs.bind(params)?))
+            }
+            Self::Not(condition) => Self::Not(Box::new(condition.bind(params)?)),
+            Self::Compare {
+                field,
+                function,
+                op,
+                value,
+            } => Self::Compare {
+                field: field.clone(),
+                function: *function,
+                op: op.clone(),
+                value: bind_value(field, *function, value, params)?,
+            },
+            Self::In {
+                field,
+                function,
+                values,
+            } => Self::In {
+                field: field.clone(),
+                function: *function,
+                values: values
+                    .iter()
+                    .map(|value| bind_value(field, *function, value, params))
+                    .collect::<Result<Vec<_>, _>>()?,
+            },
+            Self::Contains { field, value } => Self::Contains {
+                field: field.clone(),
+                value: match value {
+                    Operand::Param(number) if field == "tags" => {
+                        bind_typed("String", *number, params)?
+                    }
+                    Operand::Param(number) => bind_typed("Number", *number, params)?,
+                    value => value.clone(),
+                },
+            },
+            Self::Subtree(Operand::Param(number)) => {
+                Self::Subtree(bind_typed("Number", *number, params)?)
+            }
+            condition @ (Self::Match { .. } | Self::IsNull(_) | Self::Subtree(_)) => {
+                condition.clone()
+            }
+        })
+    }
+
+    // Узел дерева условия без потомков, для EXPLAIN
+    pub fn label(&self) -> String {
+        let target = |field: &str, function: &Option<DateFunction>| {
+            function.map_or(field.into(), |function| function.call(field))
+        };
+        match self {
+            Self::And(..) => "AND".into(),
+            Self::Or(..) => "OR".into(),
+            Self::Not(_) => "NOT".into(),
+            Self::Compare {
+                field,
+                function,
+                op,
+                value,
+            } => format!("{} {op} {value}", target(field, function)),
+            Self::Match { field, pattern } => format!("{field} ~ '{pattern}'"),
+            Self::In {
+                field,
+                function,
+                values,
+            } => format!(
+                "{} IN ({})",
+                target(field, function),
+                values
+                    .iter()
+                    .map(Operand::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Contains { field, value } => format!("{value} IN {field}"),
+            Self::IsNull(field) => format!("{field} IS NULL"),
+            Self::Subtree(root) => format!("SUBTREE({root})"),
+        }
+    }
+
+    // Поля, на которые ссылается условие
+    pub fn fields(&self) -> Vec<&str> {
+        match self {
+            Self::And(lhs, rhs) | Self::Or(lhs, rhs) => {
+                let mut fields = lhs.fields();
+                fields.extend(rhs.fields());
+                fields
+            }
+            Self::Not(condition) => condition.fields(),
+            Self::Compare { field, .. }
+            | Self::Match { field, .. }
+            | Self::In { field, .. }
+            | Self::Contains { field, .. }
+            | Self::IsNull(field) => vec![field],
+            Self::Subtree(_) => vec!["id", "parent"],
+        }
+    }
+
+    // Сравнение или IN для уже вычисленного значения поля
+    fn is_match_value(&self, lhs: &Operand) -> bool {
+        match self {
+            Self::Compare { op, value, .. } => {
+                let ordering = lhs.compare(value);
+                match op {
+                    Eq => ordering == Some(Ordering::Equal),
+                    Gt => ordering == Some(Ordering::Greater),
+                    Lt => ordering == Some(Ordering::Less),
+                    GtEq => ordering.is_some_and(Ordering::is_ge),
+                    LtEq => ordering.is_some_and(Ordering::is_le),
+                    _ => unreachable!(),
+                }
+            }
+            Self::In { values, .. } => values
+                .iter()
+                .any(|value| lhs.compare(value) == Some(Ordering::Equal)),
+            _ => unreachable!(),
+        }
+    }
+
+    // В выборку попадают задачи, для которых условие истинно, см. evaluate
+    pub fn is_match(&self, task: &Task) -> bool {
+        self.evaluate(task) == Some(true)
+    }
+
+    // Трехзначная логика SQL: сравнение с отсутствующим значением неизвестно (None),
+    // и NOT неизвестного тоже неизвестно, поэтому NOT descr = 'x' не выбирает задачи без описания
+    fn evaluate(&self, task: &Task) -> Option<bool> {
+        match self {
+            Self::And(lhs, rhs) => match lhs.evaluate(task) {
+                Some(false) => Some(false),
+                lhs => match (lhs, rhs.evaluate(task)) {
+                    (_, Some(false)) => Some(false),
+                    (Some(true), rhs) => rhs,
+                    _ => None,
+                },
+            },
+            Self::Or(lhs, rhs) => match lhs.evaluate(task) {
+                Some(true) => Some(true),
+                lhs => match (lhs, rhs.evaluate(task)) {
+                    (_, Some(true)) => Some(true),
+                    (Some(false), rhs) => rhs,
+                    _ => None,
+                },
+            },
+            Self::Not(condition) => condition.evaluate(task).map(|value| !value),
+            Self::Compare {
+                field, function, ..
+            }
+            | Self::In {
+                field, function, ..
+            } => match Operand::from_task_with(task, field, *function).unwrap() {
+                Operand::Null => None,
+                value => Some(self.is_match_value(&value)),
+            },
+            Self::Match { field, pattern } => match Operand::from_task(task, field) {
+                Some(Operand::String(value)) => Some(pattern.is_match(&value)),
+                Some(Operand::Null) => None,
+                _ => Some(false),
+            },
+            Self::Contains { field, value } => match (Operand::from_task(task, field), value) {
+                (Some(Operand::Array(values)), Operand::String(value)) => {
+                    Some(values.contains(value))
+                }
+                (Some(Operand::Ids(ids)), Operand::Number(id)) => Some(ids.contains(&(*id as u64))),
+                _ => Some(false),
+            },
+            Self::Subtree(_) => unreachable!("SUBTREE заменяется в resolve"),
+            Self::IsNull(field) => Some(task.get_value(field) == Some(serde_json::Value::Null)),
+        }
+    }
+}
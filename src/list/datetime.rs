@@ -0,0 +1,209 @@
+use super::*;
+use crate::clock;
+use chrono::{Datelike, Duration, Months, Timelike};
+use condition::Operand;
+use sqlparser::ast::{DateTimeField, Function, FunctionArguments, Interval};
+
+// Функция от поля date в левой части сравнения: DATE(date) или EXTRACT(.. FROM date)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DateFunction {
+    Date,
+    Year,
+    Quarter,
+    Month,
+    Week,
+    Day,
+    Dow,
+    IsoDow,
+    Doy,
+    Hour,
+    Minute,
+}
+
+impl DateFunction {
+    pub fn from_extract(field: &DateTimeField) -> Result<Self, ListError> {
+        Ok(match field {
+            DateTimeField::Year => Self::Year,
+            DateTimeField::Quarter => Self::Quarter,
+            DateTimeField::Month => Self::Month,
+            DateTimeField::Week(None) => Self::Week,
+            DateTimeField::Day => Self::Day,
+            DateTimeField::Dow => Self::Dow,
+            DateTimeField::Isodow => Self::IsoDow,
+            DateTimeField::Doy => Self::Doy,
+            DateTimeField::Hour => Self::Hour,
+            DateTimeField::Minute => Self::Minute,
+            field => {
+                return Err(Sql(UnhandledFunction(
+                    format!("EXTRACT({field} FROM ..)").into(),
+                )))
+            }
+        })
+    }
+
+    // Запись функции от поля, как в запросе
+    pub fn call(self, field: &str) -> String {
+        let part = match self {
+            Self::Date => return format!("DATE({field})"),
+            Self::Year => "YEAR",
+            Self::Quarter => "QUARTER",
+            Self::Month => "MONTH",
+            Self::Week => "WEEK",
+            Self::Day => "DAY",
+            Self::Dow => "DOW",
+            Self::IsoDow => "ISODOW",
+            Self::Doy => "DOY",
+            Self::Hour => "HOUR",
+            Self::Minute => "MINUTE",
+        };
+        format!("EXTRACT({part} FROM {field})")
+    }
+
+    pub fn apply(self, date: &Date) -> Operand {
+        let date = date.datetime();
+        let number = match self {
+            Self::Date => return Operand::Date(day(date)),
+            Self::Year => date.year(),
+            Self::Quarter => (date.month0() / 3 + 1) as i32,
+            Self::Month => date.month() as i32,
+            Self::Week => date.iso_week().week() as i32,
+            Self::Day => date.day() as i32,
+            // 0 - воскресенье, как в PostgreSQL
+            Self::Dow => date.weekday().num_days_from_sunday() as i32,
+            Self::IsoDow => date.weekday().number_from_monday() as i32,
+            Self::Doy => date.ordinal() as i32,
+            Self::Hour => date.hour() as i32,
+            Self::Minute => date.minute() as i32,
+        };
+        Operand::Number(number.into())
+    }
+}
+
+// День даты в поясе пользователя, как задача на весь день
+fn day(date: NaiveDateTime) -> Date {
+    Date::day(date.date(), None)
+}
+
+fn date_format(found: impl Display) -> ListError {
+    Sql(SqlError::format("[.. \"Date\"]", found))
+}
+
+// Функция без аргументов: NOW(), CURRENT_DATE
+fn function_name(function: &Function) -> Option<String> {
+    match &function.args {
+        FunctionArguments::None => Some(function.name.to_string().to_lowercase()),
+        FunctionArguments::List(list) if list.args.is_empty() => {
+            Some(function.name.to_string().to_lowercase())
+        }
+        _ => None,
+    }
+}
+
+// Значение-дата: "строка", NOW(), CURRENT_TIMESTAMP, CURRENT_DATE, DATE(..) и +/- INTERVAL
+pub fn parse_date(expr: Expr) -> Result<Date, ListError> {
+    let found = expr.to_string();
+    match expr {
+        Expr::Value(ast::Value::SingleQuotedString(_)) | Expr::Identifier(_) => {
+            condition::string_value("[.. \"Date\"]", expr)?
+                .parse::<Date>()
+                .map_err(|_| date_format(found))
+        }
+        Expr::Nested(expr) => parse_date(*expr),
+        Expr::BinaryOp {
+            left,
+            op: op @ (Plus | Minus),
+            right,
+        } => {
+            let Expr::Interval(interval) = *right else {
+                return Err(Sql(SqlError::format(
+                    "[.. \"Date\" +|- INTERVAL 'count unit']",
+                    right,
+                )));
+            };
+            let (months, duration) = parse_interval(interval)?;
+            let date = parse_date(*left)?.datetime();
+
+            let date = if let Plus = op {
+                date.checked_add_months(months)
+                    .and_then(|date| date.checked_add_signed(duration))
+            } else {
+                date.checked_sub_months(months)
+                    .and_then(|date| date.checked_sub_signed(duration))
+            };
+            date.map(Date::from).ok_or_else(|| date_format(found))
+        }
+        Expr::Function(function) => match function_name(&function).as_deref() {
+            Some("now" | "current_timestamp" | "localtimestamp") => Ok(clock::now().into()),
+            Some("current_date") => Ok(day(clock::now())),
+            _ => match function_argument(&function, "date") {
+                Some(expr) => Ok(day(parse_date(expr)?.datetime())),
+                None => Err(Sql(UnhandledFunction(function.to_string().into()))),
+            },
+        },
+        _ => Err(date_format(found)),
+    }
+}
+
+// Единственный аргумент функции с заданным именем
+pub fn function_argument(function: &Function, name: &str) -> Option<Expr> {
+    use sqlparser::ast::{FunctionArg, FunctionArgExpr};
+
+    if !function.name.to_string().eq_ignore_ascii_case(name) {
+        return None;
+    }
+    match &function.args {
+        FunctionArguments::List(list)
+            if list.duplicate_treatment.is_none() && list.clauses.is_empty() =>
+        {
+            if let [FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))] = list.args.as_slice() {
+                Some(expr.clone())
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+// INTERVAL '3 days', INTERVAL '1 month 2 hours', INTERVAL '2' DAY
+fn parse_interval(interval: Interval) -> Result<(Months, Duration), ListError> {
+    let found = interval.to_string();
+    let format = || Sql(SqlError::format("INTERVAL 'count unit'", &found));
+
+    let Expr::Value(ast::Value::SingleQuotedString(value)) = *interval.value else {
+        return Err(format());
+    };
+    let value = match interval.leading_field {
+        Some(field) if interval.last_field.is_none() => format!("{value} {field}"),
+        None => value,
+        _ => return Err(format()),
+    };
+
+    let words = value.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() || words.len() % 2 != 0 {
+        return Err(format());
+    }
+
+    // Слишком большой интервал - та же ошибка формата, а не переполнение
+    let (mut months, mut duration) = (0u32, Duration::zero());
+    for pair in words.chunks(2) {
+        let count = pair[0].parse::<u32>().map_err(|_| format())?;
+        let unit = pair[1].to_lowercase();
+        let (add_months, add_duration) = match unit.trim_end_matches('s') {
+            "minute" => (Some(0), Duration::try_minutes(count.into())),
+            "hour" => (Some(0), Duration::try_hours(count.into())),
+            "day" => (Some(0), Duration::try_days(count.into())),
+            "week" => (Some(0), Duration::try_weeks(count.into())),
+            "month" => (Some(count), Some(Duration::zero())),
+            "year" => (count.checked_mul(12), Some(Duration::zero())),
+            _ => return Err(format()),
+        };
+        months = add_months
+            .and_then(|add| months.checked_add(add))
+            .ok_or_else(format)?;
+        duration = add_duration
+            .and_then(|add| duration.checked_add(&add))
+            .ok_or_else(format)?;
+    }
+    Ok((Months::new(months), duration))
+}
@@ -0,0 +1,120 @@
+use super::*;
+use condition::Operand;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+// Столбцы, которых нет в Task: считаются по списку, только для чтения
+pub const VIRTUAL_KEYS: [&str; 1] = ["is_blocked"];
+
+impl List {
+    // Незакрытые задачи, от которых зависит task: отмененная зависимость не блокирует
+    pub fn blockers(&self, task: &Task) -> Vec<u64> {
+        task.depends_on
+            .iter()
+            .copied()
+            .filter(|id| {
+                self.index
+                    .id(*id)
+                    .is_some_and(|pos| !self.tasks[pos].status.is_closed())
+            })
+            .collect()
+    }
+
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        !self.blockers(task).is_empty()
+    }
+
+    // Значение виртуального столбца
+    pub fn virtual_value(&self, task: &Task, field: &str) -> Option<Operand> {
+        match field {
+            "is_blocked" => Some(Operand::Bool(self.is_blocked(task))),
+            _ => None,
+        }
+    }
+
+    // Зависимости должны существовать и не замыкаться на саму задачу
+    pub(crate) fn check_dependencies(&self, task: &Task) -> Result<(), ListError> {
+        if let Some(id) = task
+            .depends_on
+            .iter()
+            .find(|id| self.index.id(**id).is_none())
+        {
+            return Err(DependencyNotExist(*id));
+        }
+        if task.id == 0 {
+            return Ok(());
+        }
+
+        // Обход зависимостей от task: вернулись в task - цикл
+        let mut seen = HashSet::new();
+        let mut stack = task.depends_on.clone();
+        while let Some(id) = stack.pop() {
+            if id == task.id {
+                return Err(CyclicDependency(task.id));
+            }
+            if seen.insert(id) {
+                if let Some(pos) = self.index.id(id) {
+                    stack.extend(&self.tasks[pos].depends_on);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Задача key начинает зависеть от задач on, повторы не добавляются
+    pub fn depend(&mut self, key: String, on: &[String]) -> Result<&Task, ListError> {
+        let mut task = self.get_task(&key)?.clone();
+        for on in on {
+            let id = self.get_task(on)?.id;
+            if !task.depends_on.contains(&id) {
+                task.depends_on.push(id);
+            }
+        }
+        self.update(key, &task)
+    }
+    pub fn undepend(&mut self, key: String, on: &[String]) -> Result<&Task, ListError> {
+        let mut task = self.get_task(&key)?.clone();
+        let ids = on
+            .iter()
+            .map(|on| self.get_task(on).map(|task| task.id))
+            .collect::<Result<Vec<_>, _>>()?;
+        task.depends_on.retain(|id| !ids.contains(id));
+        self.update(key, &task)
+    }
+
+    // Незакрытые задачи в порядке зависимостей волнами: сначала доступные сейчас,
+    // затем те, что станут доступны после них, и т.д. Внутри волны - порядок списка
+    pub fn next(&self) -> Vec<&Task> {
+        // Для каждой незакрытой задачи - сколько блокирующих ее задач еще не выведено,
+        // для каждой блокирующей - позиции задач, которые она блокирует
+        let mut waiting = vec![0; self.len()];
+        let mut dependents = HashMap::<u64, Vec<usize>>::new();
+        for (pos, task) in self.iter().enumerate() {
+            if task.status.is_closed() {
+                continue;
+            }
+            for id in self.blockers(task) {
+                waiting[pos] += 1;
+                dependents.entry(id).or_default().push(pos);
+            }
+        }
+        let mut wave = (0..self.len())
+            .filter(|pos| !self.tasks[*pos].status.is_closed() && waiting[*pos] == 0)
+            .collect::<BTreeSet<_>>();
+
+        let mut order = Vec::new();
+        while !wave.is_empty() {
+            let mut next_wave = BTreeSet::new();
+            for pos in wave {
+                order.push(&self.tasks[pos]);
+                for dependent in dependents.get(&self.tasks[pos].id).into_iter().flatten() {
+                    waiting[*dependent] -= 1;
+                    if waiting[*dependent] == 0 {
+                        next_wave.insert(*dependent);
+                    }
+                }
+            }
+            wave = next_wave;
+        }
+        order
+    }
+}
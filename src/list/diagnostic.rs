@@ -0,0 +1,265 @@
+use super::*;
+use dialect::TaskDialect;
+use sqlparser::{
+    keywords::Keyword,
+    tokenizer::{Token, TokenWithLocation, Tokenizer},
+};
+
+// Место ошибки в запросе: строка и столбец (с 1) и длина в символах
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub line: u64,
+    pub column: u64,
+    pub len: usize,
+}
+
+// Фрагмент запроса, вызвавший ошибку, и его место. Ошибки сравниваются
+// по тексту фрагмента, место нужно только для подсветки
+#[derive(Clone, Debug)]
+pub struct Fragment {
+    pub text: String,
+    pub span: Option<Span>,
+}
+
+impl PartialEq for Fragment {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+    }
+}
+
+impl Display for Fragment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl From<String> for Fragment {
+    fn from(text: String) -> Self {
+        Self { text, span: None }
+    }
+}
+
+impl From<&str> for Fragment {
+    fn from(text: &str) -> Self {
+        text.to_string().into()
+    }
+}
+
+// Ошибка sqlparser, позиция берется из текста "... at Line: 1, Column: 5"
+pub fn syntax_error(sql: &str, error: ParserError) -> SqlError {
+    let message = match error {
+        ParserError::TokenizerError(message) | ParserError::ParserError(message) => message,
+        ParserError::RecursionLimitExceeded => "Слишком глубокая вложенность".into(),
+    };
+
+    let location = message
+        .rsplit_once(" at Line: ")
+        .and_then(|(message, location)| {
+            let (line, column) = location.split_once(", Column: ")?;
+            Some((
+                message.to_string(),
+                line.parse().ok()?,
+                column.parse().ok()?,
+            ))
+        });
+
+    // Без позиции ошибка относится к концу запроса
+    let (message, line, column) = location.unwrap_or_else(|| {
+        let last_line = sql.lines().last().unwrap_or_default();
+        (
+            message,
+            sql.lines().count().max(1) as u64,
+            last_line.chars().count() as u64 + 1,
+        )
+    });
+
+    Syntax {
+        message,
+        line,
+        column,
+    }
+}
+
+fn tokens(sql: &str) -> Option<Vec<TokenWithLocation>> {
+    Tokenizer::new(&TaskDialect::default(), sql)
+        .tokenize_with_location()
+        .ok()
+        .map(|tokens| {
+            tokens
+                .into_iter()
+                .filter(|token| !matches!(token.token, Token::Whitespace(_)))
+                .collect()
+        })
+}
+
+fn same_token(lhs: &Token, rhs: &Token) -> bool {
+    match (lhs, rhs) {
+        (Token::Word(lhs), Token::Word(rhs)) => {
+            lhs.quote_style == rhs.quote_style && lhs.value.eq_ignore_ascii_case(&rhs.value)
+        }
+        (lhs, rhs) => lhs == rhs,
+    }
+}
+
+// Токены запроса и позиция, до которой он разобран. Элементы запроса разбираются
+// в порядке текста, и ошибка получает место при разборе своего элемента: в
+// title = 'a' AND date = 'a' неверное значение - второе 'a', а не первое
+pub struct Locator {
+    tokens: Vec<TokenWithLocation>,
+    cursor: usize,
+}
+
+impl Locator {
+    pub fn new(sql: &str) -> Self {
+        Self {
+            tokens: tokens(sql).unwrap_or_default(),
+            cursor: 0,
+        }
+    }
+
+    // Переходит к началу предложения: FROM, WHERE, GROUP BY и т.д.
+    pub fn clause(&mut self, keyword: Keyword) {
+        if let Some(pos) = self.tokens.iter().position(|token| {
+            matches!(&token.token, Token::Word(word) if word.keyword == keyword && word.quote_style.is_none())
+        }) {
+            self.cursor = pos;
+        }
+    }
+
+    // Разбирает очередной элемент запроса. Ошибка получает место своего фрагмента
+    // внутри элемента, а следующий элемент ищется уже после этого
+    pub fn parse<E: Display, T>(
+        &mut self,
+        item: E,
+        parse: impl FnOnce(E) -> Result<T, ListError>,
+    ) -> Result<T, ListError> {
+        let range = self.find(&item.to_string(), self.cursor, self.tokens.len());
+        match parse(item) {
+            Ok(value) => {
+                if let Some((_, end)) = range {
+                    self.cursor = end;
+                }
+                Ok(value)
+            }
+            Err(error) => Err(self.locate_in(error, range)),
+        }
+    }
+
+    // Место ошибки, которая относится не к отдельному элементу, ищется от текущей позиции
+    pub fn locate(&self, error: ListError) -> ListError {
+        self.locate_in(error, None)
+    }
+
+    fn locate_in(&self, mut error: ListError, range: Option<(usize, usize)>) -> ListError {
+        let Sql(sql_error) = &mut error else {
+            return error;
+        };
+        if let Some(fragment) = sql_error.fragment_mut().filter(|f| f.span.is_none()) {
+            let (from, to) = range.unwrap_or((self.cursor, self.tokens.len()));
+            fragment.span = self
+                .find(&fragment.text, from, to)
+                .or_else(|| self.find(&fragment.text, self.cursor, self.tokens.len()))
+                .map(|range| self.span(range));
+        }
+        error
+    }
+
+    // Первое вхождение фрагмента среди токенов from..to с точностью до токенов
+    fn find(&self, fragment: &str, from: usize, to: usize) -> Option<(usize, usize)> {
+        let fragment = tokens(fragment)?;
+        if fragment.is_empty() || from >= to {
+            return None;
+        }
+        let start = self.tokens[from..to]
+            .windows(fragment.len())
+            .position(|window| {
+                window
+                    .iter()
+                    .zip(&fragment)
+                    .all(|(lhs, rhs)| same_token(&lhs.token, &rhs.token))
+            })?
+            + from;
+        Some((start, start + fragment.len()))
+    }
+
+    fn span(&self, (start, end): (usize, usize)) -> Span {
+        let (first, last) = (&self.tokens[start], &self.tokens[end - 1]);
+        let len = if first.location.line == last.location.line {
+            (last.location.column - first.location.column) as usize
+                + last.token.to_string().chars().count()
+        } else {
+            first.token.to_string().chars().count()
+        };
+
+        Span {
+            line: first.location.line,
+            column: first.location.column,
+            len,
+        }
+    }
+}
+
+impl SqlError {
+    pub fn format(expected: impl Into<String>, found: impl Display) -> Self {
+        Format {
+            expected: expected.into(),
+            found: found.to_string().into(),
+        }
+    }
+
+    // Фрагмент запроса, вызвавший ошибку
+    pub fn fragment(&self) -> Option<&Fragment> {
+        match self {
+            NonExistentField(found)
+            | NonExistentTable(found)
+            | ReadOnlyField(found)
+            | UnhandledOperator(found)
+            | NotGrouped(found)
+            | UnhandledOrderBy(found)
+            | UnhandledSelectItem(found)
+            | UnhandledFunction(found)
+            | UnhandledCondition(found)
+            | Format { found, .. } => Some(found),
+            _ => None,
+        }
+    }
+
+    fn fragment_mut(&mut self) -> Option<&mut Fragment> {
+        match self {
+            NonExistentField(found)
+            | NonExistentTable(found)
+            | ReadOnlyField(found)
+            | UnhandledOperator(found)
+            | NotGrouped(found)
+            | UnhandledOrderBy(found)
+            | UnhandledSelectItem(found)
+            | UnhandledFunction(found)
+            | UnhandledCondition(found)
+            | Format { found, .. } => Some(found),
+            _ => None,
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Syntax { line, column, .. } => Some(Span {
+                line: *line,
+                column: *column,
+                len: 1,
+            }),
+            error => error.fragment()?.span,
+        }
+    }
+}
+
+// Строка запроса с ошибкой и подчеркивание под ней
+pub fn render(sql: &str, error: &SqlError) -> Option<String> {
+    let span = error.span()?;
+    let line = sql.lines().nth(span.line as usize - 1).unwrap_or_default();
+
+    Some(format!(
+        "{line}\n{}{}",
+        " ".repeat(span.column as usize - 1),
+        "^".repeat(span.len.max(1))
+    ))
+}
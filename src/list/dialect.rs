@@ -0,0 +1,128 @@
+use sqlparser::{
+    ast::{BinaryOperator, Expr, UnaryOperator},
+    dialect::{Dialect, GenericDialect, Precedence},
+    keywords::Keyword,
+    parser::{Parser, ParserError},
+    tokenizer::Token,
+};
+use std::any::TypeId;
+
+// GenericDialect с условиями на поля-списки (tags, depends_on): 'x' [NOT] IN tags и
+// tags CONTAINS 'x'. Оба разбираются в 'x' = ANY(tags), остальное - как в GenericDialect
+#[derive(Debug, Default)]
+pub struct TaskDialect(GenericDialect);
+
+impl TaskDialect {
+    fn is_keyword(parser: &Parser, n: usize, keyword: Keyword) -> bool {
+        matches!(parser.peek_nth_token(n).token, Token::Word(word) if word.keyword == keyword)
+    }
+
+    // IN, за которым идет поле, а не список в скобках или UNNEST
+    fn is_in_field(parser: &Parser, n: usize) -> bool {
+        Self::is_keyword(parser, n, Keyword::IN)
+            && matches!(
+                parser.peek_nth_token(n + 1).token,
+                Token::Word(word) if word.keyword != Keyword::UNNEST
+            )
+    }
+
+    fn any(value: Expr, field: Expr) -> Expr {
+        Expr::AnyOp {
+            left: Box::new(value),
+            compare_op: BinaryOperator::Eq,
+            right: Box::new(field),
+        }
+    }
+}
+
+impl Dialect for TaskDialect {
+    // sqlparser проверяет диалект по типу, разбор должен совпадать с GenericDialect
+    fn dialect(&self) -> TypeId {
+        self.0.dialect()
+    }
+
+    fn parse_infix(
+        &self,
+        parser: &mut Parser,
+        expr: &Expr,
+        _precedence: u8,
+    ) -> Option<Result<Expr, ParserError>> {
+        let precedence = self.prec_value(Precedence::Between);
+        if Self::is_keyword(parser, 0, Keyword::CONTAINS) {
+            parser.next_token();
+            return Some(
+                parser
+                    .parse_subexpr(precedence)
+                    .map(|value| Self::any(value, expr.clone())),
+            );
+        }
+
+        let negated = Self::is_keyword(parser, 0, Keyword::NOT);
+        if !Self::is_in_field(parser, negated as usize) {
+            return None;
+        }
+        // [NOT] IN
+        for _ in 0..=negated as usize {
+            parser.next_token();
+        }
+        Some(parser.parse_identifier(false).map(|field| {
+            let any = Self::any(expr.clone(), Expr::Identifier(field));
+            if negated {
+                Expr::UnaryOp {
+                    op: UnaryOperator::Not,
+                    expr: Box::new(Expr::Nested(Box::new(any))),
+                }
+            } else {
+                any
+            }
+        }))
+    }
+
+    fn get_next_precedence(&self, parser: &Parser) -> Option<Result<u8, ParserError>> {
+        Self::is_keyword(parser, 0, Keyword::CONTAINS)
+            .then(|| Ok(self.prec_value(Precedence::Between)))
+    }
+
+    fn is_delimited_identifier_start(&self, ch: char) -> bool {
+        self.0.is_delimited_identifier_start(ch)
+    }
+    fn is_identifier_start(&self, ch: char) -> bool {
+        self.0.is_identifier_start(ch)
+    }
+    fn is_identifier_part(&self, ch: char) -> bool {
+        self.0.is_identifier_part(ch)
+    }
+    fn supports_unicode_string_literal(&self) -> bool {
+        self.0.supports_unicode_string_literal()
+    }
+    fn supports_group_by_expr(&self) -> bool {
+        self.0.supports_group_by_expr()
+    }
+    fn supports_connect_by(&self) -> bool {
+        self.0.supports_connect_by()
+    }
+    fn supports_match_recognize(&self) -> bool {
+        self.0.supports_match_recognize()
+    }
+    fn supports_start_transaction_modifier(&self) -> bool {
+        self.0.supports_start_transaction_modifier()
+    }
+    fn supports_window_function_null_treatment_arg(&self) -> bool {
+        self.0.supports_window_function_null_treatment_arg()
+    }
+    fn supports_dictionary_syntax(&self) -> bool {
+        self.0.supports_dictionary_syntax()
+    }
+    fn supports_window_clause_named_window_reference(&self) -> bool {
+        self.0.supports_window_clause_named_window_reference()
+    }
+    fn supports_parenthesized_set_variables(&self) -> bool {
+        self.0.supports_parenthesized_set_variables()
+    }
+    fn supports_select_wildcard_except(&self) -> bool {
+        self.0.supports_select_wildcard_except()
+    }
+    fn support_map_literal_syntax(&self) -> bool {
+        self.0.support_map_literal_syntax()
+    }
+}
@@ -0,0 +1,145 @@
+use super::*;
+use condition::Condition;
+use planner::Plan;
+
+// Узел дерева условия: сколько задач до него дошло и сколько прошло
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub depth: usize,
+    pub label: String,
+    pub input: usize,
+    pub output: usize,
+}
+
+// Результат EXPLAIN SELECT
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explain {
+    pub plan: Plan,
+    // Поля из запроса и их типы из Table
+    pub fields: Vec<(String, &'static str)>,
+    pub total: usize,
+    pub candidates: usize,
+    pub steps: Vec<Step>,
+    pub rows: usize,
+}
+
+// Проходит дерево так же, как is_match: правая часть AND получает только
+// прошедшие левую, правая часть OR - только не прошедшие
+fn explain_condition<'a>(
+    condition: &Condition,
+    tasks: Vec<&'a Task>,
+    depth: usize,
+    steps: &mut Vec<Step>,
+) -> Vec<&'a Task> {
+    let step = steps.len();
+    steps.push(Step {
+        depth,
+        label: condition.label(),
+        input: tasks.len(),
+        output: 0,
+    });
+
+    let passed = match condition {
+        Condition::And(lhs, rhs) => {
+            let passed = explain_condition(lhs, tasks, depth + 1, steps);
+            explain_condition(rhs, passed, depth + 1, steps)
+        }
+        Condition::Or(lhs, rhs) => {
+            explain_condition(lhs, tasks.clone(), depth + 1, steps);
+            let rest = tasks.iter().copied().filter(|task| !lhs.is_match(task));
+            explain_condition(rhs, rest.collect(), depth + 1, steps);
+            tasks
+                .into_iter()
+                .filter(|task| condition.is_match(task))
+                .collect()
+        }
+        // Задачи, для которых условие под NOT неизвестно, не проходят и NOT
+        Condition::Not(inner) => {
+            explain_condition(inner, tasks.clone(), depth + 1, steps);
+            tasks
+                .into_iter()
+                .filter(|task| condition.is_match(task))
+                .collect()
+        }
+        condition => tasks
+            .into_iter()
+            .filter(|task| condition.is_match(task))
+            .collect(),
+    };
+
+    steps[step].output = passed.len();
+    passed
+}
+
+impl SelectQuery {
+    pub fn explain(&self, list: &List) -> Explain {
+        let fields = self
+            .columns
+            .iter()
+            .map(|column| column.field.as_str())
+            .chain(self.condition.iter().flat_map(Condition::fields))
+            .chain(self.group_by.iter().map(String::as_str))
+            .chain(self.sort_keys.iter().map(|key| key.field.as_str()))
+            .collect::<Vec<_>>();
+        // Порядок полей как в Table, * из COUNT(*) не поле
+        let fields = Task::columns()
+            .into_iter()
+            .filter(|(key, _)| fields.contains(&key.as_str()))
+            .collect();
+
+        let condition = self.condition.as_ref().map(|c| c.resolve(list));
+        let plan = Plan::new(list, condition.as_ref());
+        let candidates = plan.tasks(list).collect::<Vec<_>>();
+        let mut steps = Vec::new();
+        if let Some(condition) = &condition {
+            explain_condition(condition, candidates.clone(), 0, &mut steps);
+        }
+
+        Explain {
+            plan,
+            fields,
+            total: list.len(),
+            candidates: candidates.len(),
+            steps,
+            rows: self.rows(list).rows.len(),
+        }
+    }
+}
+
+impl Display for Explain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "План: {}", self.plan)?;
+        writeln!(
+            f,
+            "Поля: {}",
+            self.fields
+                .iter()
+                .map(|(field, type_)| format!("{field}: {type_}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        writeln!(
+            f,
+            "Задач в списке: {}, кандидатов: {}",
+            self.total, self.candidates
+        )?;
+
+        if !self.steps.is_empty() {
+            let mut table = prettytable::Table::new();
+            table.add_row(["Условие", "Дошло", "Прошло"].iter().collect());
+            for step in &self.steps {
+                table.add_row(
+                    [
+                        format!("{}{}", "  ".repeat(step.depth), step.label),
+                        step.input.to_string(),
+                        step.output.to_string(),
+                    ]
+                    .iter()
+                    .collect(),
+                );
+            }
+            write!(f, "{table}")?;
+        }
+        write!(f, "Строк в результате: {}", self.rows)
+    }
+}
@@ -0,0 +1,236 @@
+use super::*;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+
+// Битовая карта позиций задач
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bitmap(Vec<u64>);
+
+impl Bitmap {
+    pub fn set(&mut self, pos: usize, value: bool) {
+        let (word, bit) = (pos / 64, pos % 64);
+        if word >= self.0.len() {
+            self.0.resize(word + 1, 0);
+        }
+        if value {
+            self.0[word] |= 1 << bit;
+        } else {
+            self.0[word] &= !(1 << bit);
+        }
+    }
+
+    pub fn get(&self, pos: usize) -> bool {
+        self.0
+            .get(pos / 64)
+            .is_some_and(|word| word & (1 << (pos % 64)) != 0)
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(word, bits)| {
+            (0..64)
+                .filter(move |bit| bits & (1 << bit) != 0)
+                .map(move |bit| word * 64 + bit)
+        })
+    }
+}
+
+// Индексы по полям задач: хранят отсортированные позиции задач в списке
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Index {
+    id: HashMap<u64, usize>,
+    title: HashMap<String, Vec<usize>>,
+    category: HashMap<String, Vec<usize>>,
+    // Позиции подзадач по номеру родителя
+    children: HashMap<u64, Vec<usize>>,
+    // Задачи со временем - по моменту, на весь день - по полуночи дня
+    date: BTreeMap<DateTime<Utc>, Vec<usize>>,
+    all_day: BTreeMap<NaiveDateTime, Vec<usize>>,
+    status: BTreeMap<Status, Bitmap>,
+}
+
+fn insert_position(positions: &mut Vec<usize>, pos: usize) {
+    if let Err(i) = positions.binary_search(&pos) {
+        positions.insert(i, pos);
+    }
+}
+
+fn bound_value<T>(bound: &Bound<T>) -> Option<&T> {
+    match bound {
+        Bound::Included(value) | Bound::Excluded(value) => Some(value),
+        Bound::Unbounded => None,
+    }
+}
+
+// Позиции из диапазона дат. Пустой диапазон, например date > '2024-02-01' AND date < '2024-01-01'
+fn date_range<T: Ord>(
+    dates: &BTreeMap<T, Vec<usize>>,
+    range: (Bound<T>, Bound<T>),
+) -> impl Iterator<Item = usize> + '_ {
+    let is_empty = match (bound_value(&range.0), bound_value(&range.1)) {
+        (Some(from), Some(to)) => {
+            from > to || (from == to && !matches!(range, (Bound::Included(_), Bound::Included(_))))
+        }
+        _ => false,
+    };
+    let range = if is_empty { None } else { Some(range) };
+    range
+        .into_iter()
+        .flat_map(move |range| dates.range(range))
+        .flat_map(|(_, positions)| positions.iter().copied())
+}
+
+fn remove_date<T: Ord>(dates: &mut BTreeMap<T, Vec<usize>>, date: T, pos: usize) {
+    if let Some(positions) = dates.get_mut(&date) {
+        remove_position(positions, pos);
+        if positions.is_empty() {
+            dates.remove(&date);
+        }
+    }
+}
+
+fn remove_position(positions: &mut Vec<usize>, pos: usize) {
+    if let Ok(i) = positions.binary_search(&pos) {
+        positions.remove(i);
+    }
+}
+
+impl Index {
+    pub fn new(tasks: &[Task]) -> Self {
+        let mut index = Self::default();
+        for (pos, task) in tasks.iter().enumerate() {
+            index.insert(pos, task);
+        }
+        index
+    }
+
+    // Задача на позиции pos добавлена или заменена
+    pub fn insert(&mut self, pos: usize, task: &Task) {
+        if task.id != 0 {
+            self.id.insert(task.id, pos);
+        }
+        insert_position(self.title.entry(task.title.clone()).or_default(), pos);
+        // Задачи без категории и срока в эти индексы не попадают
+        if let Some(category) = &task.category {
+            insert_position(self.category.entry(category.clone()).or_default(), pos);
+        }
+        if task.parent != 0 {
+            insert_position(self.children.entry(task.parent).or_default(), pos);
+        }
+        match &task.date {
+            Some(date) if date.is_all_day() => {
+                insert_position(self.all_day.entry(date.datetime()).or_default(), pos)
+            }
+            Some(date) => insert_position(self.date.entry(date.start()).or_default(), pos),
+            None => {}
+        }
+        self.status.entry(task.status).or_default().set(pos, true);
+    }
+
+    // Задача на позиции pos будет заменена, позиции остальных задач не меняются
+    pub fn remove(&mut self, pos: usize, task: &Task) {
+        if self.id.get(&task.id) == Some(&pos) {
+            self.id.remove(&task.id);
+        }
+        if let Some(positions) = self.title.get_mut(&task.title) {
+            remove_position(positions, pos);
+            if positions.is_empty() {
+                self.title.remove(&task.title);
+            }
+        }
+        if let Some(category) = &task.category {
+            if let Some(positions) = self.category.get_mut(category) {
+                remove_position(positions, pos);
+                if positions.is_empty() {
+                    self.category.remove(category);
+                }
+            }
+        }
+        if let Some(positions) = self.children.get_mut(&task.parent) {
+            remove_position(positions, pos);
+            if positions.is_empty() {
+                self.children.remove(&task.parent);
+            }
+        }
+        match &task.date {
+            Some(date) if date.is_all_day() => remove_date(&mut self.all_day, date.datetime(), pos),
+            Some(date) => remove_date(&mut self.date, date.start(), pos),
+            None => {}
+        }
+        if let Some(positions) = self.status.get_mut(&task.status) {
+            positions.set(pos, false);
+            if positions.count() == 0 {
+                self.status.remove(&task.status);
+            }
+        }
+    }
+
+    pub fn id(&self, id: u64) -> Option<usize> {
+        self.id.get(&id).copied()
+    }
+
+    pub fn max_id(&self) -> u64 {
+        self.id.keys().max().copied().unwrap_or_default()
+    }
+
+    pub fn title(&self, title: &str) -> &[usize] {
+        self.title.get(title).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn category(&self, category: &str) -> &[usize] {
+        self.category.get(category).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn children(&self, parent: u64) -> &[usize] {
+        self.children.get(&parent).map_or(&[], Vec::as_slice)
+    }
+
+    // Кандидаты для условия на date. Моменты отбираются точно по границам,
+    // дни - с запасом в три дня на длину дня и разницу поясов, лишние отсеет WHERE
+    pub fn date(&self, (from, to): (Bound<Date>, Bound<Date>)) -> impl Iterator<Item = usize> + '_ {
+        let timed = (
+            match &from {
+                Bound::Included(date) => Bound::Included(date.start()),
+                Bound::Excluded(date) if date.is_all_day() => Bound::Included(date.end()),
+                Bound::Excluded(date) => Bound::Excluded(date.end()),
+                Bound::Unbounded => Bound::Unbounded,
+            },
+            match &to {
+                Bound::Included(date) if date.is_all_day() => Bound::Excluded(date.end()),
+                Bound::Included(date) => Bound::Included(date.end()),
+                Bound::Excluded(date) => Bound::Excluded(date.start()),
+                Bound::Unbounded => Bound::Unbounded,
+            },
+        );
+        let margin = Duration::days(3);
+        let all_day = (
+            bound_value(&from).map_or(Bound::Unbounded, |date| {
+                Bound::Included(date.datetime() - margin)
+            }),
+            bound_value(&to).map_or(Bound::Unbounded, |date| {
+                Bound::Included(date.datetime() + margin)
+            }),
+        );
+        date_range(&self.date, timed).chain(date_range(&self.all_day, all_day))
+    }
+
+    pub fn status_count(&self, statuses: &[Status]) -> usize {
+        statuses
+            .iter()
+            .filter_map(|status| self.status.get(status))
+            .map(Bitmap::count)
+            .sum()
+    }
+
+    // Позиции задач в одном из статусов, у каждого статуса по возрастанию
+    pub fn status<'a>(&'a self, statuses: &'a [Status]) -> impl Iterator<Item = usize> + 'a {
+        statuses
+            .iter()
+            .filter_map(move |status| self.status.get(status))
+            .flat_map(Bitmap::positions)
+    }
+}
@@ -1,9 +1,35 @@
+pub mod command;
+pub mod condition;
+pub mod datetime;
+pub mod depends;
+pub mod diagnostic;
+pub mod dialect;
+pub mod explain;
+pub mod index;
+pub mod order;
+pub mod pattern;
+pub mod planner;
+pub mod prepared;
+pub mod query;
+pub mod rows;
+pub mod search;
 pub mod task;
 #[cfg(test)]
 mod tests;
+pub mod tree;
+pub mod view;
+pub mod workflow;
 
 use crate::traits::Table;
 use chrono::NaiveDateTime;
+use command::{Command, Output};
+use diagnostic::{Fragment, Locator};
+use explain::Explain;
+use index::Index;
+use prepared::Prepared;
+use query::SelectQuery;
+use rows::Rows;
+use search::{Hit, SearchIndex};
 use serde::{Deserialize, Serialize};
 use sqlparser::{
     ast::{
@@ -11,56 +37,203 @@ use sqlparser::{
         BinaryOperator::{self, *},
         Expr, SetExpr, Statement,
     },
-    dialect::GenericDialect,
     parser::{Parser, ParserError},
 };
 use std::{
-    collections::VecDeque,
     fmt::{Debug, Display},
     iter::FromIterator,
+    ops::Deref,
 };
-use std_reset::prelude::Deref;
 use task::*;
+use workflow::Workflow;
 
-#[derive(Deref, Deserialize, Serialize, Debug, PartialEq)]
-pub struct List(pub Vec<Task>);
+// Задачи в порядке файла и индексы по ним. Изменять задачи можно только
+// через методы List, чтобы индексы оставались актуальными
+#[derive(Clone, Serialize, Debug)]
+#[serde(into = "Stored")]
+pub struct List {
+    tasks: Vec<Task>,
+    index: Index,
+    // Номер для следующей добавленной задачи, не уменьшается и сохраняется в tasks.json,
+    // поэтому номер удаленной задачи не достается новой и после перезапуска
+    next_id: u64,
+    // Разрешенные переходы статусов, в tasks.json не сохраняются
+    workflow: Workflow,
+    // Загружен из tasks.json старого формата, который нужно перезаписать
+    migrated: bool,
+}
+
+// Задачи берутся как есть, номера не выдаются
+impl From<Vec<Task>> for List {
+    fn from(tasks: Vec<Task>) -> Self {
+        let index = Index::new(&tasks);
+        let next_id = index.max_id() + 1;
+        Self {
+            tasks,
+            index,
+            next_id,
+            workflow: Workflow::default(),
+            migrated: false,
+        }
+    }
+}
+
+// Содержимое tasks.json: задачи и номер для следующей задачи.
+// Старые tasks.json - просто массив задач
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum Stored {
+    List { next_id: u64, tasks: Vec<Task> },
+    Tasks(Vec<Task>),
+}
+
+// При загрузке задачи без номера (из старого tasks.json) или с повторным номером
+// получают новый. Старым считается и файл с датами без смещения от UTC или с is_done
+impl<'de> Deserialize<'de> for List {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (stored, mut migrated) = task::with_legacy(|| Stored::deserialize(deserializer));
+        let (mut tasks, stored_id) = match stored? {
+            Stored::List { next_id, tasks } => (tasks, next_id),
+            Stored::Tasks(tasks) => {
+                migrated = true;
+                (tasks, 0)
+            }
+        };
+        let max_id = tasks.iter().map(|task| task.id).max().unwrap_or_default();
+        let mut next_id = stored_id.max(max_id + 1);
+        let mut seen = std::collections::HashSet::new();
+        for task in &mut tasks {
+            if task.id == 0 || !seen.insert(task.id) {
+                task.id = next_id;
+                next_id += 1;
+                migrated = true;
+            }
+        }
+        Ok(List {
+            next_id,
+            migrated,
+            ..List::from(tasks)
+        })
+    }
+}
+
+impl From<List> for Stored {
+    fn from(list: List) -> Self {
+        Stored::List {
+            next_id: list.next_id,
+            tasks: list.tasks,
+        }
+    }
+}
+
+impl From<List> for Vec<Task> {
+    fn from(list: List) -> Self {
+        list.tasks
+    }
+}
+
+impl Deref for List {
+    type Target = Vec<Task>;
+    fn deref(&self) -> &Self::Target {
+        &self.tasks
+    }
+}
+
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        self.tasks == other.tasks
+    }
+}
 
 impl Display for List {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut table = prettytable::Table::new();
         table.add_row(Task::get_keys().iter().collect());
-        self.iter().for_each(|task| {
-            table.add_row(
+        // Подзадачи под родителем с отступом, у родителя - сколько подзадач выполнено
+        for (pos, depth) in self.tree() {
+            let task = &self.tasks[pos];
+            table.add_row(prettytable::Row::new(
                 task.get_entries()
                     .iter()
-                    .map(|(key, value)| Task::format_by_key(&key, value.to_string()))
+                    .map(|(key, value)| match key.as_str() {
+                        "title" => {
+                            let indent = match depth {
+                                0 => String::new(),
+                                depth => format!("{}└ ", "  ".repeat(depth - 1)),
+                            };
+                            let progress = match self.progress(task.id) {
+                                (_, 0) => String::new(),
+                                (done, total) => format!(" [{done}/{total}]"),
+                            };
+                            prettytable::Cell::new(&format!(
+                                "{indent}{}{progress}",
+                                Task::format_by_key(key, value.to_string())
+                            ))
+                        }
+                        key => Task::cell_by_key(key, value),
+                    })
                     .collect(),
-            );
-        });
+            ));
+        }
         write!(f, "{}", table)
     }
 }
 
 impl<'a> FromIterator<&'a Task> for List {
     fn from_iter<T: IntoIterator<Item = &'a Task>>(iter: T) -> Self {
-        Self(iter.into_iter().cloned().collect::<Vec<_>>())
+        Self::from(iter.into_iter().cloned().collect::<Vec<_>>())
     }
 }
 
 #[derive(Debug, PartialEq)]
 pub enum SqlError {
     NotValidQuery,
-    NonExistentField(String),
-    UnhandledOperator(BinaryOperator),
-    Format(String),
+    Syntax {
+        message: String,
+        line: u64,
+        column: u64,
+    },
+    NonExistentField(Fragment),
+    NonExistentTable(Fragment),
+    MissingField(String),
+    ReadOnlyField(Fragment),
+    UnhandledOperator(Fragment),
+    UnhandledOrderBy(Fragment),
+    UnhandledSelectItem(Fragment),
+    NotGrouped(Fragment),
+    InvalidRegex(String),
+    ParamCount {
+        expected: usize,
+        found: usize,
+    },
+    ParamType {
+        number: usize,
+        expected: String,
+        found: String,
+    },
+    UnhandledFunction(Fragment),
+    UnhandledCondition(Fragment),
+    Format {
+        expected: String,
+        found: Fragment,
+    },
 }
 
 #[derive(Debug, PartialEq)]
 pub enum ListError {
     TaskAlreadyExists,
     TaskAlreadyCompleted,
+    TaskAlreadyInStatus(Status),
+    IllegalTransition(Status, Status),
     TaskNotChanged,
     TaskNotExist(String),
+    AmbiguousTitle(String, Vec<u64>),
+    ViewNotExist(String),
+    ParentNotExist(u64),
+    CyclicParent(u64),
+    DependencyNotExist(u64),
+    CyclicDependency(u64),
+    TaskBlocked(Vec<u64>),
     Sql(SqlError),
 }
 
@@ -74,16 +247,80 @@ impl Display for ListError {
             "{}",
             match self {
                 TaskAlreadyCompleted => "Задача уже выполнена".into(),
+                TaskAlreadyInStatus(status) => format!("Задача уже в статусе {status}"),
+                IllegalTransition(from, to) => {
+                    format!("Переход из статуса {from} в {to} не разрешен")
+                }
                 TaskNotExist(title) => format!("Задача \"{title}\" не найдена"),
+                AmbiguousTitle(title, ids) => format!(
+                    "Задач с названием \"{title}\" несколько, укажите id: {}",
+                    ids.iter()
+                        .map(u64::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
                 TaskAlreadyExists => "Задача уже существует".into(),
                 TaskNotChanged => "Задача не изменена".into(),
+                ViewNotExist(name) => format!("Представление \"{name}\" не найдено"),
+                ParentNotExist(id) => format!("Родительская задача {id} не найдена"),
+                CyclicParent(id) => {
+                    format!("Задача {id} не может быть подзадачей самой себя или своей подзадачи")
+                }
+                DependencyNotExist(id) => format!("Задача {id} из зависимостей не найдена"),
+                CyclicDependency(id) => format!("Зависимости задачи {id} образуют цикл"),
+                TaskBlocked(ids) => format!(
+                    "Сначала нужно выполнить задачи: {}",
+                    ids.iter()
+                        .map(u64::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
                 Sql(a) => match a {
-                    NotValidQuery => {
-                        "Ожидаемый формат запроса: SELECT * [WHERE where_condition]".to_string()
-                    }
-                    NonExistentField(m) => m.to_string(),
+                    NotValidQuery => concat!(
+                        "Ожидаемый формат запроса:\n",
+                        "  [EXPLAIN] SELECT * | field | COUNT | MIN | MAX [AS alias], .. [FROM tasks]",
+                        " [WHERE where_condition] [GROUP BY field, ..]",
+                        " [ORDER BY field [ASC | DESC], ..] [LIMIT count] [OFFSET count]\n",
+                        "  INSERT INTO tasks [(field, ..)] VALUES (value, ..), ..\n",
+                        "  UPDATE tasks SET field = value, .. [WHERE where_condition]\n",
+                        "  DELETE FROM tasks [WHERE where_condition]"
+                    )
+                    .to_string(),
+                    Syntax {
+                        message,
+                        line,
+                        column,
+                    } => format!("Синтаксическая ошибка ({line}:{column}): {message}"),
+                    NonExistentField(field) => format!(
+                        "Поле {field} не существует, ожидается одно из: {}",
+                        Task::get_keys().join(", ")
+                    ),
+                    NonExistentTable(table) =>
+                        format!("Таблица {table} не найдена, ожидается tasks"),
+                    MissingField(field) => format!("Не указано обязательное поле {field}"),
+                    ReadOnlyField(field) => format!("Поле {field} нельзя изменить"),
                     UnhandledOperator(op) => format!("Оператор {op} не обрабатывается"),
-                    Format(format) => format!("Ожидается формат: {format}"),
+                    UnhandledOrderBy(expr) => format!("Сортировка по {expr} не обрабатывается"),
+                    UnhandledSelectItem(item) => format!("Столбец {item} не обрабатывается"),
+                    UnhandledFunction(function) => {
+                        format!("Функция {function} не обрабатывается")
+                    }
+                    UnhandledCondition(expr) => format!("Условие {expr} не обрабатывается"),
+                    InvalidRegex(e) => format!("Некорректное регулярное выражение: {e}"),
+                    ParamCount { expected, found } => {
+                        format!("Запрос ожидает параметров: {expected}, передано: {found}")
+                    }
+                    ParamType {
+                        number,
+                        expected,
+                        found,
+                    } => format!("Параметр ${number} должен быть {expected}, передан {found}"),
+                    NotGrouped(field) => format!(
+                        "Столбец {field} должен быть в GROUP BY или внутри COUNT | MIN | MAX"
+                    ),
+                    Format { expected, found } => {
+                        format!("Ожидается формат: {expected}, найдено: {found}")
+                    }
                 },
             }
         )
@@ -91,216 +328,160 @@ impl Display for ListError {
 }
 
 impl List {
-    pub fn get_task(&self, title: &str) -> Result<&Task, ListError> {
-        self.iter()
-            .find(|task| task.title == title)
-            .ok_or(TaskNotExist(title.into()))
+    pub fn index(&self) -> &Index {
+        &self.index
     }
-    pub fn add(&mut self, task: Task) -> Result<&Task, ListError> {
-        if !self.iter().any(|t| *t == task) {
-            self.push(task);
-            Ok(self.last().as_ref().unwrap())
-        } else {
-            Err(TaskAlreadyExists)
+    // Нужно ли перезаписать tasks.json в новом формате, даже если задачи не менялись
+    pub fn is_migrated(&self) -> bool {
+        self.migrated
+    }
+    // Задача по id или по названию; одинаковые названия - ошибка
+    fn position(&self, key: &str) -> Result<usize, ListError> {
+        // Число может быть и id одной задачи, и названием другой - тогда выбрать нельзя
+        let by_id = key.parse().ok().and_then(|id| self.index.id(id));
+        let positions = by_id
+            .into_iter()
+            .chain(
+                self.index
+                    .title(key)
+                    .iter()
+                    .copied()
+                    .filter(|pos| Some(*pos) != by_id),
+            )
+            .collect::<Vec<_>>();
+        match positions.as_slice() {
+            [] => Err(TaskNotExist(key.into())),
+            [pos] => Ok(*pos),
+            positions => Err(AmbiguousTitle(
+                key.into(),
+                positions.iter().map(|pos| self.tasks[*pos].id).collect(),
+            )),
         }
     }
-    pub fn done(&mut self, title: String) -> Result<&Task, ListError> {
-        let task = self
-            .iter_mut()
-            .find(|task| task.title == title)
-            .ok_or(TaskNotExist(title))?;
-
-        if task.is_done {
-            Err(TaskAlreadyCompleted)
-        } else {
-            task.is_done = true;
-            Ok(task)
+    // Заменяет задачу на позиции pos, обновляя индексы
+    pub(crate) fn replace(&mut self, pos: usize, task: Task) -> &Task {
+        self.index.remove(pos, &self.tasks[pos]);
+        self.index.insert(pos, &task);
+        self.tasks[pos] = task;
+        &self.tasks[pos]
+    }
+    // Оставляет только подходящие задачи, позиции сдвигаются, поэтому индексы строятся заново.
+    // Зависимости от удаленных задач убираются
+    pub(crate) fn retain(&mut self, f: impl FnMut(&Task) -> bool) {
+        self.tasks.retain(f);
+        self.index = Index::new(&self.tasks);
+        let index = &self.index;
+        for task in &mut self.tasks {
+            task.depends_on.retain(|id| index.id(*id).is_some());
         }
     }
-    pub fn update(&mut self, title: String, task: &Task) -> Result<&Task, ListError> {
-        let finded_task = self
-            .iter_mut()
-            .find(|task| task.title == title)
-            .ok_or(TaskNotExist(title))?;
+    pub fn get_task(&self, key: &str) -> Result<&Task, ListError> {
+        self.position(key).map(|pos| &self.tasks[pos])
+    }
+    // Выдает задаче новый номер; задача с тем же содержимым уже есть - ошибка
+    pub fn add(&mut self, task: Task) -> Result<&Task, ListError> {
+        if self
+            .index
+            .title(&task.title)
+            .iter()
+            .any(|pos| self.tasks[*pos].same_content(&task))
+        {
+            return Err(TaskAlreadyExists);
+        }
+        self.check_parent(&task)?;
+        self.check_dependencies(&task)?;
+        let task = Task {
+            id: self.next_id,
+            ..task
+        };
+        self.next_id += 1;
+        self.index.insert(self.tasks.len(), &task);
+        self.tasks.push(task);
+        Ok(self.tasks.last().unwrap())
+    }
+    // Переход в статус done, см. set_status
+    pub fn done(&mut self, key: String) -> Result<&Task, ListError> {
+        self.set_status(key, Status::Done)
+    }
+    // id задачи не меняется, статус - по правилам workflow, как в set_status
+    pub fn update(&mut self, key: String, task: &Task) -> Result<&Task, ListError> {
+        let pos = self.position(&key)?;
 
-        if *finded_task == *task {
+        if self.tasks[pos].same_content(task) {
             Err(TaskNotChanged)
         } else {
-            *finded_task = task.clone();
-            Ok(finded_task)
+            let mut task = Task {
+                id: self.tasks[pos].id,
+                ..task.clone()
+            };
+            self.check_parent(&task)?;
+            self.check_dependencies(&task)?;
+            let status = self.tasks[pos].status;
+            if status != task.status {
+                self.check_transition(status, &task)?;
+                if task.status == Status::Done {
+                    task = self.complete(task);
+                }
+            }
+            Ok(self.replace(pos, task))
         }
     }
-    pub fn delete(&mut self, title: String) -> Result<Task, ListError> {
-        if let Some(index) = self
-            .clone()
-            .into_iter()
-            .position(|task| task.title == title)
-        {
-            Ok(self.remove(index))
-        } else {
-            Err(TaskNotExist(title))
-        }
+    // Уже имеющиеся теги не дублируются
+    pub fn tag(&mut self, key: String, tags: &[String]) -> Result<&Task, ListError> {
+        let pos = self.position(&key)?;
+        let mut task = self.tasks[pos].clone();
+        Task::add_tags(&mut task.tags, tags.iter().map(String::as_str));
+        self.update(key, &task)
+    }
+    pub fn untag(&mut self, key: String, tags: &[String]) -> Result<&Task, ListError> {
+        let pos = self.position(&key)?;
+        let mut task = self.tasks[pos].clone();
+        task.tags.retain(|tag| !tags.contains(tag));
+        self.update(key, &task)
     }
+    // Удаляет задачу вместе со всеми подзадачами
+    pub fn delete(&mut self, key: String) -> Result<Task, ListError> {
+        let pos = self.position(&key)?;
+        let subtree = self.subtree(self.tasks[pos].id);
+        let task = self.tasks.remove(pos);
+        self.retain(|task| !subtree.contains(&task.id));
+        Ok(task)
+    }
+    // Задачи целиком, подходящие под запрос
     pub fn select(&self, sql: &str) -> Result<List, ListError> {
-        // Парсим AST из sql
-        let select = Parser::parse_sql(&GenericDialect {}, sql)
-            .and_then(|ast| {
-                ast.get(0)
-                    .and_then(|stmt| {
-                        if let Statement::Query(query) = stmt {
-                            Some(query.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .ok_or_else(|| ParserError::ParserError("".into()))
-            })
-            .and_then(|query| {
-                if let SetExpr::Select(select) = (*query.body).clone() {
-                    Ok(select)
-                } else {
-                    Err(ParserError::ParserError("".into()))
-                }
-            })
-            .map_err(|_| Sql(NotValidQuery))?;
-
-        // Если WHERE выражения нет, то вернуть весь список
-        let Some(where_) = select.selection else {
-            return Ok(List((*self).clone()));
+        let query = sql.parse::<SelectQuery>()?;
+        Ok(List::from(query.tasks(self)))
+    }
+    // Только запрошенные в SELECT столбцы и агрегаты
+    pub fn query(&self, sql: &str) -> Result<Rows, ListError> {
+        Ok(sql.parse::<SelectQuery>()?.rows(self))
+    }
+    // Как выполняется SELECT: план, типы полей и число задач после каждого условия.
+    // Ключевое слово EXPLAIN перед запросом необязательно
+    pub fn explain(&self, sql: &str) -> Result<Explain, ListError> {
+        let (statement, mut locator) = command::parse_statement(sql)?;
+        let statement = match statement {
+            Statement::Explain { statement, .. } => *statement,
+            statement => statement,
         };
-
-        let mut curr_list = (*self).clone();
-        let mut query = VecDeque::from([where_]);
-
-        let binary_op_check = |left| {
-            if let Expr::Identifier(ident) = left {
-                Ok(ident.value)
-            } else {
-                Err(Sql(Format("WHERE [Identifier ..]".into())))
-            }
+        let Statement::Query(_) = statement else {
+            return Err(Sql(NotValidQuery));
         };
-
-        let check_string = |format, right| {
-            match right {
-                Expr::Value(ast::Value::SingleQuotedString(value)) => Some(value),
-                Expr::Identifier(ident) => {
-                    matches!(ident.quote_style, Some('"')).then(|| ident.value)
-                }
-                _ => None,
-            }
-            .ok_or(Sql(Format(format)))
-        };
-
-        while let Some(expr) = query.pop_front() {
-            match expr {
-                Expr::Nested(expr) => {
-                    query.push_back((*expr).clone());
-                    continue;
-                }
-                Expr::BinaryOp { left, right, op } => {
-                    let (left, right) = ((*left).clone(), (*right).clone());
-                    match op {
-                        And => {
-                            query.push_back(left);
-                            query.push_back(right);
-                        }
-                        Eq | Gt | Lt | GtEq | LtEq => {
-                            let left = binary_op_check(left)?;
-
-                            let not_eq_check = || {
-                                if let Eq = op {
-                                    Ok(())
-                                } else {
-                                    Err(Sql(Format(format!("{} = ..", left.as_str()))))
-                                }
-                            };
-
-                            match left.as_str() {
-                                "title" => {
-                                    not_eq_check()?;
-                                    let value = check_string(format!("[.. StringValue]"), right)?;
-                                    curr_list = filter(curr_list, |task| &task.title, &op, &value);
-                                }
-                                "descr" => {
-                                    not_eq_check()?;
-                                    let value = check_string(format!("[.. StringValue]"), right)?;
-                                    curr_list = filter(curr_list, |task| &task.descr, &op, &value);
-                                }
-                                "date" => {
-                                    let value = check_string(format!("[.. \"Date\"]"), right)
-                                        .and_then(|value| {
-                                            value
-                                                .parse::<Date>()
-                                                .map_err(|_| Sql(Format(format!("[.. \"Date\"]"))))
-                                        })?;
-                                    curr_list = filter(curr_list, |task| &task.date, &op, &value);
-                                }
-                                "category" => {
-                                    not_eq_check()?;
-                                    let value = check_string(format!("[.. StringValue]"), right)?;
-                                    curr_list =
-                                        filter(curr_list, |task| &task.category, &op, &value);
-                                }
-                                "is_done" => {
-                                    not_eq_check()?;
-
-                                    if let Expr::Value(ast::Value::Boolean(value)) = right.clone() {
-                                        curr_list =
-                                            filter(curr_list, |task| &task.is_done, &op, &value);
-                                    } else {
-                                        return Err(Sql(Format(format!("[.. true | false]"))));
-                                    }
-                                }
-                                _ => return Err(Sql(NonExistentField(left))),
-                            };
-                        }
-                        op => return Err(Sql(UnhandledOperator(op))),
-                    }
-                }
-                Expr::Like { expr, pattern, .. } => {
-                    let left = binary_op_check(*expr)
-                        .map_err(|_| Sql(Format("[StringIdnetifier like ..]".into())))?;
-
-                    if ["title", "descr", "category"].contains(&left.as_str()) {
-                        let value = check_string(format!("[.. like StringValue]"), *pattern)?;
-                        curr_list = curr_list
-                            .into_iter()
-                            .filter(|task| {
-                                task.get_value(&left).unwrap().to_string().contains(&value)
-                            })
-                            .collect();
-                    } else {
-                        return Err(Sql(Format("[StringIdnetifier like ..]".into())));
-                    }
-                }
-                _ => return Err(Sql(NotValidQuery)),
-            }
+        match Prepared::from_statement(statement, &mut locator)?.bind(&[])? {
+            Command::Select(query) => Ok(query.explain(self)),
+            _ => Err(Sql(NotValidQuery)),
         }
-
-        Ok(List(curr_list))
     }
-}
-
-fn filter<T: PartialEq + PartialOrd>(
-    list: Vec<Task>,
-    lhs: fn(&Task) -> &T,
-    op: &BinaryOperator,
-    rhs: &T,
-) -> Vec<Task> {
-    list.clone()
-        .into_iter()
-        .filter(|task| {
-            let lhs = lhs(task);
-            match op {
-                Eq => lhs == rhs,
-                Gt => lhs > rhs,
-                Lt => lhs < rhs,
-                GtEq => lhs >= rhs,
-                LtEq => lhs <= rhs,
-                _ => {
-                    unreachable!()
-                }
-            }
-        })
-        .collect()
+    // Полнотекстовый поиск по title, category и descr с ранжированием
+    pub fn search(&self, query: &str) -> Vec<Hit<'_>> {
+        SearchIndex::new(self).search(self, query)
+    }
+    // Запрос с параметрами ? или $1 для многократного выполнения
+    pub fn prepare(sql: &str) -> Result<Prepared, ListError> {
+        sql.parse()
+    }
+    // SELECT, INSERT, UPDATE или DELETE
+    pub fn execute(&mut self, sql: &str) -> Result<Output, ListError> {
+        sql.parse::<Command>()?.execute(self)
+    }
 }
@@ -0,0 +1,87 @@
+use super::*;
+use condition::Operand;
+use sqlparser::ast::OrderByExpr;
+use std::cmp::Ordering;
+
+// Ключ сортировки из ORDER BY
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortKey {
+    pub field: String,
+    pub desc: bool,
+    // Задачи без значения поля: по умолчанию в конце при любом направлении
+    pub nulls_first: bool,
+}
+
+impl SortKey {
+    pub fn parse(order_by: OrderByExpr) -> Result<Self, ListError> {
+        let OrderByExpr {
+            expr,
+            asc,
+            nulls_first,
+            with_fill: None,
+        } = order_by
+        else {
+            return Err(Sql(UnhandledOrderBy(order_by.to_string().into())));
+        };
+
+        let Expr::Identifier(ident) = expr else {
+            return Err(Sql(UnhandledOrderBy(expr.to_string().into())));
+        };
+
+        if !Task::has_column(&ident.value) {
+            return Err(Sql(NonExistentField(ident.value.into())));
+        }
+
+        Ok(Self {
+            field: ident.value,
+            desc: asc == Some(false),
+            nulls_first: nulls_first == Some(true),
+        })
+    }
+
+    fn cmp(&self, lhs: &Task, rhs: &Task) -> Ordering {
+        let (lhs, rhs) = (
+            Operand::from_task(lhs, &self.field),
+            Operand::from_task(rhs, &self.field),
+        );
+        let nulls = match (&lhs, &rhs) {
+            (Some(Operand::Null), Some(Operand::Null)) => return Ordering::Equal,
+            (Some(Operand::Null), _) => Ordering::Greater,
+            (_, Some(Operand::Null)) => Ordering::Less,
+            _ => Ordering::Equal,
+        };
+        if nulls.is_ne() {
+            return if self.nulls_first {
+                nulls.reverse()
+            } else {
+                nulls
+            };
+        }
+
+        let ordering = lhs.partial_cmp(&rhs).unwrap_or(Ordering::Equal);
+        if self.desc {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+// Стабильная сортировка: задачи с равными ключами сохраняют порядок из файла
+pub fn sort(tasks: &mut [Task], keys: &[SortKey]) {
+    tasks.sort_by(|lhs, rhs| {
+        keys.iter()
+            .map(|key| key.cmp(lhs, rhs))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+// Значение LIMIT / OFFSET
+pub fn parse_count(clause: &str, expr: Expr) -> Result<usize, ListError> {
+    match &expr {
+        Expr::Value(ast::Value::Number(number, _)) => number.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| Sql(SqlError::format(format!("{clause} [Number]"), expr)))
+}
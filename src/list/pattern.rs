@@ -0,0 +1,82 @@
+use super::*;
+use regex::{Regex, RegexBuilder};
+
+// Скомпилированный шаблон LIKE / ILIKE / REGEXP
+#[derive(Clone, Debug)]
+pub struct Pattern(Regex);
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Pattern {
+    // % - любая последовательность символов, _ - ровно один символ
+    pub fn like(
+        pattern: &str,
+        escape: Option<String>,
+        case_insensitive: bool,
+    ) -> Result<Self, ListError> {
+        let escape = match escape {
+            Some(escape) => {
+                let mut chars = escape.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(char), None) => Some(char),
+                    _ => {
+                        return Err(Sql(SqlError::format(
+                            "[.. ESCAPE Char]",
+                            format!("'{escape}'"),
+                        )))
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let mut regex = String::from("^");
+        let mut chars = pattern.chars();
+        while let Some(char) = chars.next() {
+            match char {
+                char if Some(char) == escape => {
+                    let escaped = chars.next().ok_or_else(|| {
+                        Sql(SqlError::format(
+                            "[.. ESCAPE Char Char]",
+                            format!("'{pattern}'"),
+                        ))
+                    })?;
+                    regex.push_str(&regex::escape(&escaped.to_string()));
+                }
+                '%' => regex.push_str(".*"),
+                '_' => regex.push('.'),
+                char => regex.push_str(&regex::escape(&char.to_string())),
+            }
+        }
+        regex.push('$');
+
+        Self::build(&regex, case_insensitive)
+    }
+
+    pub fn regex(pattern: &str, case_insensitive: bool) -> Result<Self, ListError> {
+        Self::build(pattern, case_insensitive)
+    }
+
+    fn build(regex: &str, case_insensitive: bool) -> Result<Self, ListError> {
+        RegexBuilder::new(regex)
+            .case_insensitive(case_insensitive)
+            .dot_matches_new_line(true)
+            .build()
+            .map(Self)
+            .map_err(|e| Sql(InvalidRegex(e.to_string())))
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        self.0.is_match(value)
+    }
+}
@@ -0,0 +1,289 @@
+use super::*;
+use condition::{Condition, Operand};
+use std::{cmp::Ordering, ops::Bound};
+
+// Условие, которое можно ответить по индексу
+#[derive(Clone, Debug, PartialEq)]
+pub enum Lookup {
+    Id(Vec<u64>),
+    Title(Vec<String>),
+    Category(Vec<String>),
+    Date(Bound<Date>, Bound<Date>),
+    Status(Vec<Status>),
+}
+
+// Способ получения задач-кандидатов, которые затем проверяются всем WHERE
+#[derive(Clone, Debug, PartialEq)]
+pub enum Plan {
+    FullScan,
+    Index { lookup: Lookup, estimate: usize },
+}
+
+impl Display for Lookup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |values: &[String]| {
+            values
+                .iter()
+                .map(|value| Operand::String(value.clone()).to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Self::Id(ids) => write!(
+                f,
+                "id IN ({})",
+                ids.iter()
+                    .map(u64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Title(titles) => write!(f, "title IN ({})", list(titles)),
+            Self::Category(categories) => write!(f, "category IN ({})", list(categories)),
+            Self::Date(from, to) => {
+                match from {
+                    Bound::Included(date) => write!(f, "'{date}' <= ")?,
+                    Bound::Excluded(date) => write!(f, "'{date}' < ")?,
+                    Bound::Unbounded => {}
+                }
+                write!(f, "date")?;
+                match to {
+                    Bound::Included(date) => write!(f, " <= '{date}'"),
+                    Bound::Excluded(date) => write!(f, " < '{date}'"),
+                    Bound::Unbounded => Ok(()),
+                }
+            }
+            Self::Status(statuses) => write!(
+                f,
+                "status IN ({})",
+                statuses
+                    .iter()
+                    .map(|status| Operand::Status(*status).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FullScan => write!(f, "полный перебор"),
+            Self::Index { lookup, estimate } => {
+                write!(f, "индекс по {lookup}, задач: {estimate}")
+            }
+        }
+    }
+}
+
+// Задачи без номера (id 0) в индексе нет, поэтому id = 0 отвечается перебором
+fn ids(values: &[Operand]) -> Option<Vec<u64>> {
+    if values.contains(&Operand::Number(0)) {
+        return None;
+    }
+    Some(
+        values
+            .iter()
+            .filter_map(|value| match value {
+                Operand::Number(value) if *value > 0 => Some(*value as u64),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+fn strings(values: &[Operand]) -> Vec<String> {
+    values
+        .iter()
+        .filter_map(|value| match value {
+            Operand::String(value) => Some(value.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+// Слагаемые верхнего уровня в цепочке AND
+fn conjuncts(condition: &Condition) -> Vec<&Condition> {
+    match condition {
+        Condition::And(lhs, rhs) => {
+            let mut result = conjuncts(lhs);
+            result.extend(conjuncts(rhs));
+            result
+        }
+        condition => vec![condition],
+    }
+}
+
+fn bound_value(bound: &Bound<Date>) -> Option<&Date> {
+    match bound {
+        Bound::Included(date) | Bound::Excluded(date) => Some(date),
+        Bound::Unbounded => None,
+    }
+}
+
+// Более узкая из двух границ: нижняя - большая, верхняя - меньшая,
+// при равных значениях строгая
+fn narrower(lhs: Bound<Date>, rhs: Bound<Date>, lower: bool) -> Bound<Date> {
+    let ordering = match (bound_value(&lhs), bound_value(&rhs)) {
+        (None, _) => return rhs,
+        (_, None) => return lhs,
+        (Some(lhs), Some(rhs)) => lhs.cmp(rhs),
+    };
+    match ordering {
+        Ordering::Greater if lower => lhs,
+        Ordering::Less if !lower => lhs,
+        Ordering::Equal if matches!(lhs, Bound::Excluded(_)) => lhs,
+        _ => rhs,
+    }
+}
+
+// Диапазоны дат из разных слагаемых пересекаются в один: date >= a AND date < b
+fn merge_dates(lookups: Vec<Lookup>) -> Vec<Lookup> {
+    let mut range: Option<(Bound<Date>, Bound<Date>)> = None;
+    let mut merged = Vec::new();
+    for lookup in lookups {
+        match (lookup, range.take()) {
+            (Lookup::Date(from, to), None) => range = Some((from, to)),
+            (Lookup::Date(from, to), Some((lower, upper))) => {
+                range = Some((narrower(lower, from, true), narrower(upper, to, false)))
+            }
+            (lookup, current) => {
+                range = current;
+                merged.push(lookup);
+            }
+        }
+    }
+    merged.extend(range.map(|(from, to)| Lookup::Date(from, to)));
+    merged
+}
+
+impl Lookup {
+    fn from_condition(condition: &Condition) -> Option<Self> {
+        match condition {
+            Condition::Compare {
+                field,
+                function: None,
+                op,
+                value,
+            } => match (field.as_str(), op, value) {
+                ("id", Eq, value) => ids(std::slice::from_ref(value)).map(Self::Id),
+                ("title", Eq, Operand::String(value)) => Some(Self::Title(vec![value.clone()])),
+                ("category", Eq, Operand::String(value)) => {
+                    Some(Self::Category(vec![value.clone()]))
+                }
+                ("status", Eq, Operand::Status(status)) => Some(Self::Status(vec![*status])),
+                // is_done - вычисляемый столбец, отвечается индексом по статусу
+                ("is_done", Eq, Operand::Bool(value)) => Some(Self::Status(
+                    Status::ALL
+                        .iter()
+                        .copied()
+                        .filter(|status| (*status == Status::Done) == *value)
+                        .collect(),
+                )),
+                ("date", op, Operand::Date(date)) => {
+                    let (included, excluded) =
+                        (Bound::Included(date.clone()), Bound::Excluded(date.clone()));
+                    Some(match op {
+                        Eq => Self::Date(included.clone(), included),
+                        Gt => Self::Date(excluded, Bound::Unbounded),
+                        GtEq => Self::Date(included, Bound::Unbounded),
+                        Lt => Self::Date(Bound::Unbounded, excluded),
+                        LtEq => Self::Date(Bound::Unbounded, included),
+                        _ => return None,
+                    })
+                }
+                _ => None,
+            },
+            Condition::In {
+                field,
+                function: None,
+                values,
+            } => match field.as_str() {
+                "id" => ids(values).map(Self::Id),
+                "title" => Some(Self::Title(strings(values))),
+                "category" => Some(Self::Category(strings(values))),
+                "status" => Some(Self::Status(
+                    values
+                        .iter()
+                        .filter_map(|value| match value {
+                            Operand::Status(status) => Some(*status),
+                            _ => None,
+                        })
+                        .collect(),
+                )),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn estimate(&self, list: &List) -> usize {
+        let index = list.index();
+        match self {
+            Self::Id(ids) => ids.iter().filter_map(|id| index.id(*id)).count(),
+            Self::Title(titles) => titles.iter().map(|title| index.title(title).len()).sum(),
+            Self::Category(categories) => categories
+                .iter()
+                .map(|category| index.category(category).len())
+                .sum(),
+            Self::Date(from, to) => index.date((from.clone(), to.clone())).count(),
+            Self::Status(statuses) => index.status_count(statuses),
+        }
+    }
+
+    // Позиции задач по возрастанию, без повторов
+    fn positions(&self, list: &List) -> Vec<usize> {
+        let index = list.index();
+        let mut positions = match self {
+            Self::Id(ids) => ids.iter().filter_map(|id| index.id(*id)).collect(),
+            Self::Title(titles) => titles
+                .iter()
+                .flat_map(|title| index.title(title).iter().copied())
+                .collect::<Vec<_>>(),
+            Self::Category(categories) => categories
+                .iter()
+                .flat_map(|category| index.category(category).iter().copied())
+                .collect(),
+            Self::Date(from, to) => index.date((from.clone(), to.clone())).collect(),
+            Self::Status(statuses) => index.status(statuses).collect(),
+        };
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+}
+
+impl Plan {
+    // Индекс для самого избирательного слагаемого AND, если он лучше полного перебора
+    pub fn new(list: &List, condition: Option<&Condition>) -> Self {
+        let lookups = condition
+            .map(conjuncts)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(Lookup::from_condition)
+            .collect();
+
+        merge_dates(lookups)
+            .into_iter()
+            .map(|lookup| (lookup.estimate(list), lookup))
+            .filter(|(estimate, _)| *estimate < list.len())
+            .min_by_key(|(estimate, _)| *estimate)
+            .map_or(Self::FullScan, |(estimate, lookup)| Self::Index {
+                lookup,
+                estimate,
+            })
+    }
+
+    // Кандидаты в порядке списка, условие WHERE к ним еще не применено
+    pub fn tasks<'a>(&self, list: &'a List) -> Box<dyn Iterator<Item = &'a Task> + 'a> {
+        match self {
+            Self::FullScan => Box::new(list.iter()),
+            Self::Index { lookup, .. } => Box::new(
+                lookup
+                    .positions(list)
+                    .into_iter()
+                    .map(move |pos| &list[pos]),
+            ),
+        }
+    }
+}
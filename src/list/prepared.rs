@@ -0,0 +1,98 @@
+use super::*;
+use condition::Operand;
+use sqlparser::ast::{visit_expressions_mut, Value};
+use std::{ops::ControlFlow, str::FromStr};
+
+// Подготовленный запрос: SQL разбирается один раз, а значения параметров ? или $1
+// подставляются при каждом выполнении с проверкой типа по полю
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prepared {
+    command: Command,
+    params: usize,
+}
+
+impl FromStr for Prepared {
+    type Err = ListError;
+    fn from_str(sql: &str) -> Result<Self, Self::Err> {
+        let (statement, mut locator) = command::parse_statement(sql)?;
+        Self::from_statement(statement, &mut locator)
+    }
+}
+
+impl Prepared {
+    pub fn from_statement(
+        mut statement: Statement,
+        locator: &mut Locator,
+    ) -> Result<Self, ListError> {
+        // ? нумеруются по порядку и превращаются в $1, $2, ..; смешивать стили нельзя
+        let (mut positional, mut numbered) = (0, 0);
+        let flow = visit_expressions_mut(&mut statement, |expr| {
+            if let Expr::Value(Value::Placeholder(placeholder)) = expr {
+                let number = placeholder
+                    .strip_prefix('$')
+                    .and_then(|number| number.parse::<usize>().ok())
+                    .filter(|number| *number > 0);
+                match (placeholder.as_str(), number) {
+                    ("?", _) if numbered == 0 => {
+                        positional += 1;
+                        *placeholder = format!("${positional}");
+                    }
+                    (_, Some(number)) if positional == 0 => numbered = numbered.max(number),
+                    _ => {
+                        let expected = match (positional, numbered) {
+                            (0, 0) => "? | $Number",
+                            (0, _) => "$Number",
+                            _ => "?",
+                        };
+                        return ControlFlow::Break(SqlError::format(expected, &placeholder));
+                    }
+                }
+            }
+            ControlFlow::Continue(())
+        });
+        if let ControlFlow::Break(e) = flow {
+            return Err(locator.locate(Sql(e)));
+        }
+
+        Ok(Self {
+            command: Command::from_statement(statement, locator)?,
+            params: positional.max(numbered),
+        })
+    }
+
+    // Количество параметров, которое ожидает запрос
+    pub fn params(&self) -> usize {
+        self.params
+    }
+
+    pub fn bind(&self, params: &[Operand]) -> Result<Command, ListError> {
+        if params.len() != self.params {
+            return Err(Sql(ParamCount {
+                expected: self.params,
+                found: params.len(),
+            }));
+        }
+        self.command.bind(params)
+    }
+
+    fn bind_select(&self, params: &[Operand]) -> Result<SelectQuery, ListError> {
+        if let Command::Select(query) = self.bind(params)? {
+            Ok(query)
+        } else {
+            Err(Sql(NotValidQuery))
+        }
+    }
+
+    // Задачи целиком, подходящие под запрос
+    pub fn select(&self, list: &List, params: &[Operand]) -> Result<List, ListError> {
+        Ok(List::from(self.bind_select(params)?.tasks(list)))
+    }
+    // Только запрошенные в SELECT столбцы и агрегаты
+    pub fn query(&self, list: &List, params: &[Operand]) -> Result<Rows, ListError> {
+        Ok(self.bind_select(params)?.rows(list))
+    }
+    // SELECT, INSERT, UPDATE или DELETE
+    pub fn execute(&self, list: &mut List, params: &[Operand]) -> Result<Output, ListError> {
+        self.bind(params)?.execute(list)
+    }
+}
@@ -0,0 +1,252 @@
+use super::*;
+use condition::{Condition, Operand};
+use order::SortKey;
+use planner::Plan;
+use prepared::Prepared;
+use rows::{Aggregate, Column, Rows};
+use sqlparser::{
+    ast::{
+        Function, FunctionArg, FunctionArgExpr, FunctionArguments, GroupByExpr, Query, SelectItem,
+    },
+    keywords::Keyword,
+};
+use std::str::FromStr;
+
+// Разобранный SELECT запрос
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectQuery {
+    pub columns: Vec<Column>,
+    pub condition: Option<Condition>,
+    pub group_by: Vec<String>,
+    pub sort_keys: Vec<SortKey>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl FromStr for SelectQuery {
+    type Err = ListError;
+    fn from_str(sql: &str) -> Result<Self, Self::Err> {
+        let (statement, mut locator) = command::parse_statement(sql)?;
+        if !matches!(statement, Statement::Query(_)) {
+            return Err(Sql(NotValidQuery));
+        }
+        match Prepared::from_statement(statement, &mut locator)?.bind(&[])? {
+            Command::Select(query) => Ok(query),
+            _ => Err(Sql(NotValidQuery)),
+        }
+    }
+}
+
+fn field(expr: Expr) -> Result<Column, ListError> {
+    let Expr::Identifier(ident) = expr else {
+        return Err(Sql(UnhandledSelectItem(expr.to_string().into())));
+    };
+    if Task::has_column(&ident.value) {
+        Ok(Column::new(&ident.value))
+    } else {
+        Err(Sql(NonExistentField(ident.value.into())))
+    }
+}
+
+// COUNT(*), COUNT(field), MIN(field), MAX(field)
+fn aggregate(expr: Expr) -> Result<Column, ListError> {
+    let unhandled = Sql(UnhandledSelectItem(expr.to_string().into()));
+    let name = expr.to_string();
+
+    let Expr::Function(Function {
+        name: function,
+        args: FunctionArguments::List(list),
+        filter: None,
+        over: None,
+        ..
+    }) = expr
+    else {
+        return Err(unhandled);
+    };
+    let aggregate = match function.to_string().to_lowercase().as_str() {
+        "count" => Aggregate::Count,
+        "min" => Aggregate::Min,
+        "max" => Aggregate::Max,
+        _ => return Err(unhandled),
+    };
+    if list.duplicate_treatment.is_some() || !list.clauses.is_empty() {
+        return Err(unhandled);
+    }
+
+    let field = match list.args.as_slice() {
+        [FunctionArg::Unnamed(FunctionArgExpr::Wildcard)] if aggregate == Aggregate::Count => {
+            "*".to_string()
+        }
+        [FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))] => field(expr.clone())?.field,
+        _ => return Err(unhandled),
+    };
+
+    Ok(Column {
+        name,
+        field,
+        aggregate: Some(aggregate),
+    })
+}
+
+fn parse_select_item(item: SelectItem) -> Result<Vec<Column>, ListError> {
+    // Виртуальные столбцы можно только выводить, не группировать и не агрегировать
+    let column = |expr: Expr| match expr {
+        Expr::Function(_) => aggregate(expr),
+        Expr::Identifier(ident) if depends::VIRTUAL_KEYS.contains(&ident.value.as_str()) => {
+            Ok(Column::new(&ident.value))
+        }
+        expr => field(expr),
+    };
+
+    match item {
+        SelectItem::Wildcard(_) => Ok(Task::get_keys()
+            .iter()
+            .map(|key| Column::new(key))
+            .collect()),
+        SelectItem::UnnamedExpr(expr) => Ok(vec![column(expr)?]),
+        SelectItem::ExprWithAlias { expr, alias } => Ok(vec![Column {
+            name: alias.value,
+            ..column(expr)?
+        }]),
+        item => Err(Sql(UnhandledSelectItem(item.to_string().into()))),
+    }
+}
+
+impl SelectQuery {
+    // Предложения разбираются не в порядке текста, поэтому locator переходит к каждому
+    pub fn from_query(query: Query, locator: &mut Locator) -> Result<Self, ListError> {
+        let SetExpr::Select(select) = *query.body else {
+            return Err(Sql(NotValidQuery));
+        };
+        let select = *select;
+
+        locator.clause(Keyword::FROM);
+        match select.from.as_slice() {
+            [] => {}
+            [table] => command::check_table(table).map_err(|e| locator.locate(e))?,
+            _ => return Err(Sql(NotValidQuery)),
+        }
+
+        locator.clause(Keyword::SELECT);
+        let mut columns = vec![];
+        for item in select.projection {
+            columns.extend(locator.parse(item, parse_select_item)?);
+        }
+
+        locator.clause(Keyword::GROUP);
+        let group_by = match select.group_by {
+            GroupByExpr::Expressions(exprs, modifiers) if modifiers.is_empty() => exprs
+                .into_iter()
+                .map(|expr| locator.parse(expr, |expr| field(expr).map(|column| column.field)))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(Sql(NotValidQuery)),
+        };
+        if select.having.is_some() {
+            return Err(Sql(NotValidQuery));
+        }
+
+        // При группировке каждый столбец должен быть либо в GROUP BY, либо агрегатом
+        if !group_by.is_empty() || columns.iter().any(|column| column.aggregate.is_some()) {
+            if let Some(column) = columns
+                .iter()
+                .find(|column| column.aggregate.is_none() && !group_by.contains(&column.field))
+            {
+                locator.clause(Keyword::SELECT);
+                return Err(locator.locate(Sql(NotGrouped(column.field.clone().into()))));
+            }
+        }
+
+        locator.clause(Keyword::WHERE);
+        let condition = select
+            .selection
+            .map(|expr| Condition::parse(expr, locator))
+            .transpose()?;
+        locator.clause(Keyword::ORDER);
+        let sort_keys = query
+            .order_by
+            .map(|order_by| {
+                order_by
+                    .exprs
+                    .into_iter()
+                    .map(|order_by| locator.parse(order_by, SortKey::parse))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+        locator.clause(Keyword::OFFSET);
+        let offset = query
+            .offset
+            .map(|offset| locator.parse(offset.value, |expr| order::parse_count("OFFSET", expr)))
+            .transpose()?
+            .unwrap_or(0);
+        locator.clause(Keyword::LIMIT);
+        let limit = query
+            .limit
+            .map(|limit| locator.parse(limit, |expr| order::parse_count("LIMIT", expr)))
+            .transpose()?;
+
+        Ok(Self {
+            columns,
+            condition,
+            group_by,
+            sort_keys,
+            offset,
+            limit,
+        })
+    }
+
+    fn is_grouped(&self) -> bool {
+        !self.group_by.is_empty() || self.columns.iter().any(|c| c.aggregate.is_some())
+    }
+
+    // Запрос с подставленными в WHERE значениями параметров
+    pub fn bind(&self, params: &[Operand]) -> Result<Self, ListError> {
+        Ok(Self {
+            condition: self
+                .condition
+                .as_ref()
+                .map(|condition| condition.bind(params))
+                .transpose()?,
+            ..self.clone()
+        })
+    }
+
+    // Отфильтрованные и отсортированные задачи без учета LIMIT / OFFSET
+    fn filtered(&self, list: &List) -> Vec<Task> {
+        // Если WHERE выражения нет, то берем весь список,
+        // иначе кандидатов по самому избирательному индексу
+        let condition = self.condition.as_ref().map(|c| c.resolve(list));
+        let mut tasks = Plan::new(list, condition.as_ref())
+            .tasks(list)
+            .filter(|task| condition.as_ref().is_none_or(|c| c.is_match(task)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        order::sort(&mut tasks, &self.sort_keys);
+        tasks
+    }
+
+    fn page<T>(&self, items: Vec<T>) -> Vec<T> {
+        items
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    // Задачи, попавшие в выборку
+    pub fn tasks(&self, list: &List) -> Vec<Task> {
+        self.page(self.filtered(list))
+    }
+
+    // Строки результата: проекция задач или, при агрегатах и GROUP BY, по строке на группу
+    pub fn rows(&self, list: &List) -> Rows {
+        if self.is_grouped() {
+            let mut rows = Rows::group(self.columns.clone(), &self.group_by, &self.filtered(list));
+            rows.rows = self.page(rows.rows);
+            rows
+        } else {
+            Rows::project(self.columns.clone(), &self.tasks(list), list)
+        }
+    }
+}
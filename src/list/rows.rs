@@ -0,0 +1,144 @@
+use super::*;
+use condition::Operand;
+use serde_json::Value;
+
+// Агрегатная функция столбца
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregate {
+    Count,
+    Min,
+    Max,
+}
+
+// Столбец результата запроса
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    // Заголовок столбца (имя поля или псевдоним из AS)
+    pub name: String,
+    // Поле задачи, из которого берется значение ("*" для COUNT(*))
+    pub field: String,
+    pub aggregate: Option<Aggregate>,
+}
+
+impl Column {
+    pub fn new(field: &str) -> Self {
+        Self {
+            name: field.into(),
+            field: field.into(),
+            aggregate: None,
+        }
+    }
+
+    fn aggregate(&self, tasks: &[&Task]) -> Value {
+        // COUNT(field), MIN и MAX пропускают задачи без значения поля
+        let present = tasks
+            .iter()
+            .copied()
+            .filter(|task| {
+                self.aggregate.is_none()
+                    || Operand::from_task(task, &self.field) != Some(Operand::Null)
+            })
+            .collect::<Vec<_>>();
+        let extremum = |ordering| {
+            present
+                .iter()
+                .copied()
+                .reduce(|acc, task| {
+                    let (lhs, rhs) = (
+                        Operand::from_task(task, &self.field),
+                        Operand::from_task(acc, &self.field),
+                    );
+                    if lhs.partial_cmp(&rhs) == Some(ordering) {
+                        task
+                    } else {
+                        acc
+                    }
+                })
+                .and_then(|task| task.column_value(&self.field))
+                .unwrap_or(Value::Null)
+        };
+
+        match self.aggregate {
+            None => tasks
+                .first()
+                .and_then(|task| task.column_value(&self.field))
+                .unwrap_or(Value::Null),
+            Some(Aggregate::Count) => Value::from(present.len()),
+            Some(Aggregate::Min) => extremum(std::cmp::Ordering::Less),
+            Some(Aggregate::Max) => extremum(std::cmp::Ordering::Greater),
+        }
+    }
+}
+
+// Результат запроса: только запрошенные столбцы
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rows {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Rows {
+    // Значения виртуальных столбцов берутся из списка
+    pub fn project(columns: Vec<Column>, tasks: &[Task], list: &List) -> Self {
+        let rows = tasks
+            .iter()
+            .map(|task| {
+                columns
+                    .iter()
+                    .map(|column| match list.virtual_value(task, &column.field) {
+                        Some(Operand::Bool(value)) => Value::Bool(value),
+                        _ => task.column_value(&column.field).unwrap(),
+                    })
+                    .collect()
+            })
+            .collect();
+        Self { columns, rows }
+    }
+
+    // Группировка по значениям group_by; без GROUP BY все задачи составляют одну группу
+    pub fn group(columns: Vec<Column>, group_by: &[String], tasks: &[Task]) -> Self {
+        let mut groups: Vec<(Vec<Value>, Vec<&Task>)> = vec![];
+        for task in tasks {
+            let key = group_by
+                .iter()
+                .map(|field| task.column_value(field).unwrap())
+                .collect::<Vec<_>>();
+            match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+                Some((_, group)) => group.push(task),
+                None => groups.push((key, vec![task])),
+            }
+        }
+        // Без GROUP BY агрегаты дают одну строку даже для пустой выборки
+        if group_by.is_empty() && groups.is_empty() {
+            groups.push((vec![], vec![]));
+        }
+
+        let rows = groups
+            .iter()
+            .map(|(_, group)| {
+                columns
+                    .iter()
+                    .map(|column| column.aggregate(group))
+                    .collect()
+            })
+            .collect();
+        Self { columns, rows }
+    }
+}
+
+impl Display for Rows {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut table = prettytable::Table::new();
+        table.add_row(self.columns.iter().map(|column| &column.name).collect());
+        self.rows.iter().for_each(|row| {
+            table.add_row(prettytable::Row::new(
+                self.columns
+                    .iter()
+                    .zip(row)
+                    .map(|(column, value)| Task::cell_by_key(&column.field, value))
+                    .collect(),
+            ));
+        });
+        write!(f, "{}", table)
+    }
+}
@@ -0,0 +1,210 @@
+use super::*;
+use condition::Operand;
+use rust_stemmers::{Algorithm, Stemmer};
+use std::{collections::HashMap, ops::Range};
+
+// Поля, по которым идет поиск, и их вес в релевантности
+const FIELDS: [(&str, f64); 3] = [("title", 3.0), ("category", 2.0), ("descr", 1.0)];
+
+// Параметры BM25
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+// Слово текста и его нормализованная основа
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub range: Range<usize>,
+    pub term: String,
+}
+
+// Разбивает текст на слова, приводит к нижнему регистру и отрезает окончания:
+// кириллица русским стеммером, остальное английским
+pub struct Tokenizer {
+    russian: Stemmer,
+    english: Stemmer,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Self {
+            russian: Stemmer::create(Algorithm::Russian),
+            english: Stemmer::create(Algorithm::English),
+        }
+    }
+}
+
+impl Tokenizer {
+    pub fn tokens(&self, text: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut start = None;
+        for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+            match (c.is_alphanumeric(), start) {
+                (true, None) => start = Some(i),
+                (false, Some(from)) => {
+                    tokens.push(Token {
+                        range: from..i,
+                        term: self.term(&text[from..i]),
+                    });
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        tokens
+    }
+
+    fn term(&self, word: &str) -> String {
+        let word = word.to_lowercase().replace('ё', "е");
+        let stemmer = if word.chars().any(|c| matches!(c, 'а'..='я')) {
+            &self.russian
+        } else {
+            &self.english
+        };
+        stemmer.stem(&word).into_owned()
+    }
+
+    // Оборачивает слова с основой из terms в mark, остальной текст не меняется
+    pub fn highlight(&self, text: &str, terms: &[String], mark: impl Fn(&str) -> String) -> String {
+        let mut highlighted = String::new();
+        let mut last = 0;
+        for Token { range, term } in self.tokens(text) {
+            if terms.contains(&term) {
+                highlighted.push_str(&text[last..range.start]);
+                highlighted.push_str(&mark(&text[range.clone()]));
+                last = range.end;
+            }
+        }
+        highlighted.push_str(&text[last..]);
+        highlighted
+    }
+}
+
+// Вхождение основы в поле задачи
+#[derive(Clone, Debug, PartialEq)]
+struct Posting {
+    pos: usize,
+    field: usize,
+    count: usize,
+}
+
+// Обратный индекс: основа -> задачи и поля, в которых она встречается
+pub struct SearchIndex {
+    tokenizer: Tokenizer,
+    postings: HashMap<String, Vec<Posting>>,
+    // Длина каждого поля каждой задачи в словах
+    lengths: Vec<[usize; FIELDS.len()]>,
+}
+
+// Найденная задача
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hit<'a> {
+    pub task: &'a Task,
+    pub score: f64,
+    // Основы из запроса, найденные в задаче
+    pub terms: Vec<String>,
+}
+
+impl SearchIndex {
+    pub fn new(tasks: &[Task]) -> Self {
+        let tokenizer = Tokenizer::default();
+        let mut postings = HashMap::<String, Vec<Posting>>::new();
+        let mut lengths = Vec::with_capacity(tasks.len());
+
+        for (pos, task) in tasks.iter().enumerate() {
+            let mut task_lengths = [0; FIELDS.len()];
+            for (field, (key, _)) in FIELDS.iter().enumerate() {
+                let Some(Operand::String(text)) = Operand::from_task(task, key) else {
+                    continue;
+                };
+                let tokens = tokenizer.tokens(&text);
+                task_lengths[field] = tokens.len();
+
+                let mut counts = HashMap::<String, usize>::new();
+                for token in tokens {
+                    *counts.entry(token.term).or_default() += 1;
+                }
+                for (term, count) in counts {
+                    postings
+                        .entry(term)
+                        .or_default()
+                        .push(Posting { pos, field, count });
+                }
+            }
+            lengths.push(task_lengths);
+        }
+
+        Self {
+            tokenizer,
+            postings,
+            lengths,
+        }
+    }
+
+    pub fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
+
+    // Задачи с хотя бы одним словом запроса, по убыванию релевантности (BM25 с весами полей)
+    pub fn search<'a>(&self, tasks: &'a [Task], query: &str) -> Vec<Hit<'a>> {
+        let total = self.lengths.len() as f64;
+        let average = |field: usize| {
+            let sum = self
+                .lengths
+                .iter()
+                .map(|lengths| lengths[field])
+                .sum::<usize>();
+            (sum as f64 / total).max(1.0)
+        };
+        let averages = (0..FIELDS.len()).map(average).collect::<Vec<_>>();
+
+        let mut terms = Vec::new();
+        for token in self.tokenizer.tokens(query) {
+            if !terms.contains(&token.term) {
+                terms.push(token.term);
+            }
+        }
+
+        let mut hits = HashMap::<usize, (f64, Vec<String>)>::new();
+        for term in terms {
+            let Some(postings) = self.postings.get(&term) else {
+                continue;
+            };
+            let mut found = postings
+                .iter()
+                .map(|posting| posting.pos)
+                .collect::<Vec<_>>();
+            found.dedup();
+            let frequency = found.len() as f64;
+            let idf = (1.0 + (total - frequency + 0.5) / (frequency + 0.5)).ln();
+
+            for Posting { pos, field, count } in postings {
+                let count = *count as f64;
+                let length = self.lengths[*pos][*field] as f64 / averages[*field];
+                let score = FIELDS[*field].1 * idf * count * (K1 + 1.0)
+                    / (count + K1 * (1.0 - B + B * length));
+
+                let hit = hits.entry(*pos).or_default();
+                hit.0 += score;
+                if !hit.1.contains(&term) {
+                    hit.1.push(term.clone());
+                }
+            }
+        }
+
+        let mut hits = hits
+            .into_iter()
+            .map(|(pos, (score, terms))| (pos, score, terms))
+            .collect::<Vec<_>>();
+        // При равной релевантности - порядок списка
+        hits.sort_by(|(lhs_pos, lhs, _), (rhs_pos, rhs, _)| {
+            rhs.total_cmp(lhs).then(lhs_pos.cmp(rhs_pos))
+        });
+        hits.into_iter()
+            .map(|(pos, score, terms)| Hit {
+                task: &tasks[pos],
+                score,
+                terms,
+            })
+            .collect()
+    }
+}
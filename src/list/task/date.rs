@@ -1,26 +1,300 @@
-use std::str::FromStr;
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
 
-use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Clone, Deserialize, Serialize, Debug, Default, PartialEq, PartialOrd)]
-pub struct Date(NaiveDateTime);
+use crate::clock;
+
+// Момент времени в UTC или весь день, и пояс, в котором дата была задана (если был указан явно).
+// Моменты сравниваются без учета пояса: 12:00 по Москве и 11:00 по Берлину - одна дата.
+// В tasks.json хранится в RFC 3339 с поясом в скобках: 2024-08-20T12:00:00+03:00[Europe/Moscow],
+// день - без времени: 2024-08-20
+#[derive(Clone, Debug)]
+pub struct Date {
+    value: Value,
+    zone: Option<Tz>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Value {
+    Time(DateTime<Utc>),
+    // Задача на весь день: срок - до конца дня в поясе задачи или пользователя
+    Day(NaiveDate),
+}
+
+impl Default for Date {
+    fn default() -> Self {
+        DateTime::<Utc>::default().into()
+    }
+}
+
+// Местное время в поясе. Время из перехода на летнее время сдвигается на час вперед,
+// из повторяющегося часа берется первое
+fn resolve(datetime: NaiveDateTime, zone: Tz) -> DateTime<Utc> {
+    zone.from_local_datetime(&datetime)
+        .earliest()
+        .or_else(|| {
+            zone.from_local_datetime(&(datetime + Duration::hours(1)))
+                .earliest()
+        })
+        .map_or_else(|| datetime.and_utc(), |date| date.with_timezone(&Utc))
+}
+
+// Местное время пользователя
+impl From<NaiveDateTime> for Date {
+    fn from(date: NaiveDateTime) -> Self {
+        Date::in_zone(date, None)
+    }
+}
+
+impl From<DateTime<Utc>> for Date {
+    fn from(instant: DateTime<Utc>) -> Self {
+        Date {
+            value: Value::Time(instant),
+            zone: None,
+        }
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.value {
+            Value::Time(_) => write!(f, "{}", self.datetime().format("%Y-%m-%d %H:%M")),
+            Value::Day(day) => write!(f, "{}", day.format("%Y-%m-%d")),
+        }
+    }
+}
+
+// Для хранения и сортировки: по началу, день раньше момента в его полночь.
+// Для условий WHERE есть compare
+impl PartialEq for Date {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Date {}
+
+impl PartialOrd for Date {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Date {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl Hash for Date {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl Date {
+    // Местное время в поясе zone, без пояса - в поясе пользователя
+    pub fn in_zone(datetime: NaiveDateTime, zone: Option<Tz>) -> Self {
+        Date {
+            value: Value::Time(resolve(datetime, zone.unwrap_or_else(clock::zone))),
+            zone,
+        }
+    }
+
+    // Весь день в поясе zone, без пояса - в поясе пользователя
+    pub fn day(day: NaiveDate, zone: Option<Tz>) -> Self {
+        Date {
+            value: Value::Day(day),
+            zone,
+        }
+    }
+
+    pub fn is_all_day(&self) -> bool {
+        matches!(self.value, Value::Day(_))
+    }
+
+    fn key(&self) -> (DateTime<Utc>, bool) {
+        (self.start(), !self.is_all_day())
+    }
+
+    fn own_zone(&self) -> Tz {
+        self.zone.unwrap_or_else(clock::zone)
+    }
+
+    // Начало срока: момент или полночь дня
+    pub fn start(&self) -> DateTime<Utc> {
+        match self.value {
+            Value::Time(instant) => instant,
+            Value::Day(day) => resolve(day.and_hms_opt(0, 0, 0).unwrap(), self.own_zone()),
+        }
+    }
+
+    // Конец срока: момент или полночь следующего дня, не включая ее
+    pub fn end(&self) -> DateTime<Utc> {
+        match self.value {
+            Value::Time(instant) => instant,
+            Value::Day(day) => resolve(
+                day.succ_opt().unwrap_or(day).and_hms_opt(0, 0, 0).unwrap(),
+                self.own_zone(),
+            ),
+        }
+    }
+
+    // Сравнение в условиях: день равен любому моменту внутри него, раньше - только если
+    // закончился до него. Задача на весь день 2024-08-20 не просрочена в 00:01 этого дня
+    pub fn compare(&self, other: &Self) -> Ordering {
+        let before = |lhs: &Date, rhs: &Date| {
+            lhs.end() < rhs.start() || (lhs.is_all_day() && lhs.end() == rhs.start())
+        };
+        if before(self, other) {
+            Ordering::Less
+        } else if before(other, self) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+
+    // Время в поясе пользователя, у дня - полночь
+    pub fn datetime(&self) -> NaiveDateTime {
+        match self.value {
+            Value::Time(instant) => instant.with_timezone(&clock::zone()).naive_local(),
+            Value::Day(day) => day.and_hms_opt(0, 0, 0).unwrap(),
+        }
+    }
+
+    pub fn zone(&self) -> Option<Tz> {
+        self.zone
+    }
+
+    // Время в исходном поясе задачи, без него - в поясе пользователя, у дня - полночь
+    pub fn local(&self) -> NaiveDateTime {
+        match self.value {
+            Value::Time(instant) => instant.with_timezone(&self.own_zone()).naive_local(),
+            Value::Day(day) => day.and_hms_opt(0, 0, 0).unwrap(),
+        }
+    }
+
+    // Та же дата в другое местное время: день остается днем, пояс сохраняется
+    pub fn with_local(&self, datetime: NaiveDateTime) -> Self {
+        match self.value {
+            Value::Time(_) => Date::in_zone(datetime, self.zone),
+            Value::Day(_) => Date::day(datetime.date(), self.zone),
+        }
+    }
+}
+
+// Пояс в конце строки: "2024-08-20 12:00 Europe/Berlin" или "2024-08-20T12:00:00+02:00[Europe/Berlin]"
+fn split_zone(s: &str) -> Result<(&str, Option<Tz>), String> {
+    if let Some(rest) = s.strip_suffix(']') {
+        let (date, zone) = rest
+            .rsplit_once('[')
+            .ok_or("Ожидается пояс: [Europe/Moscow]")?;
+        let zone = zone
+            .parse::<Tz>()
+            .map_err(|_| format!("Неизвестный часовой пояс: {zone}"))?;
+        return Ok((date.trim_end(), Some(zone)));
+    }
+    match s.rsplit_once(' ') {
+        Some((date, zone)) if zone.contains('/') || zone.eq_ignore_ascii_case("UTC") => {
+            let zone = zone
+                .parse::<Tz>()
+                .map_err(|_| format!("Неизвестный часовой пояс: {zone}"))?;
+            Ok((date.trim_end(), Some(zone)))
+        }
+        _ => Ok((s, None)),
+    }
+}
+
+// Дата и признак, что время записано без смещения от UTC, как в старых tasks.json
+fn parse(s: &str) -> Result<(Date, bool), String> {
+    let (s, zone) = split_zone(s.trim())?;
+
+    // Со смещением от UTC: 2024-08-20T12:00:00+03:00, 2024-08-20 12:00+03, 2024-08-20 09:00Z
+    let offset = s
+        .strip_suffix(['Z', 'z'])
+        .map_or_else(|| s.to_string(), |s| format!("{s}+00:00"));
+    let offset_formats = [
+        "%Y-%m-%dT%H:%M:%S%.f%#z",
+        "%Y-%m-%d %H:%M:%S%#z",
+        "%Y-%m-%d %H:%M%#z",
+    ];
+    for format in offset_formats {
+        if let Ok(date) = DateTime::parse_from_str(&offset, format) {
+            let date = Date {
+                value: Value::Time(date.with_timezone(&Utc)),
+                zone,
+            };
+            return Ok((date, false));
+        }
+    }
+
+    // Без смещения - местное время в указанном поясе или в поясе пользователя.
+    // Так же читаются даты из tasks.json, сохраненные до появления поясов
+    let formats = &vec![
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y/%m/%d %H:%M:%S",
+    ];
+    for format in formats {
+        if let Ok(date) = NaiveDateTime::parse_from_str(s, format) {
+            return Ok((Date::in_zone(date, zone), true));
+        }
+    }
+
+    // Без времени - задача на весь день
+    let day_formats = ["%Y-%m-%d", "%Y/%m/%d"];
+    for format in day_formats {
+        if let Ok(day) = NaiveDate::parse_from_str(s, format) {
+            return Ok((Date::day(day, zone), false));
+        }
+    }
+    Err(format!(
+        "Ожидается формат: {} | {}, можно со смещением (+03:00) или поясом (Europe/Moscow)",
+        formats.join(" | "),
+        day_formats.join(" | ")
+    ))
+}
 
 impl FromStr for Date {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let formats = &vec![
-            "%Y-%m-%d %H:%M:%S",
-            "%Y-%m-%d %H:%M",
-            "%Y-%m-%d",
-            "%Y-%m-%dT%H:%M:%S",
-            "%Y/%m/%d %H:%M:%S",
-        ];
-        for format in formats {
-            if let Ok(date) = NaiveDateTime::parse_from_str(s, format) {
-                return Ok(Date(date));
-            }
-        }
-        Err(format!("Ожидается формат: {}", formats.join(" | ")))
+        parse(s).map(|(date, _)| date)
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let date = match (self.value, self.zone) {
+            (Value::Time(instant), Some(zone)) => instant
+                .with_timezone(&zone)
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+            (Value::Time(instant), None) => instant.to_rfc3339_opts(SecondsFormat::Secs, true),
+            (Value::Day(day), _) => day.format("%Y-%m-%d").to_string(),
+        };
+        let value = match self.zone {
+            Some(zone) => format!("{date}[{}]", zone.name()),
+            None => date,
+        };
+        serializer.serialize_str(&value)
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (date, naive) =
+            parse(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)?;
+        if naive {
+            super::legacy();
+        }
+        Ok(date)
     }
 }
@@ -1,17 +1,81 @@
 pub mod date;
+pub mod natural;
+pub mod priority;
+pub mod recurrence;
+pub mod status;
 
 use super::*;
 pub use date::Date;
 use macros::Table;
+pub use priority::Priority;
+pub use recurrence::Recurrence;
+pub use status::Status;
+use std::cell::Cell;
 use std_reset::prelude::Default;
 
 #[derive(Clone, Deserialize, Serialize, Debug, Default, PartialEq, Table)]
 pub struct Task {
+    // Номер задачи в списке, выдается в List::add; 0 - еще не выдан.
+    // В старых tasks.json поля нет
+    #[serde(default)]
+    pub id: u64,
     pub title: String,
-    pub descr: String,
-    pub date: Date,
-    pub category: String,
-    pub is_done: bool,
+    // Описание, срок и категория необязательны: у идей и задач "когда-нибудь" срока нет.
+    // Пустые строки из старых tasks.json читаются как отсутствие значения
+    #[serde(default, deserialize_with = "non_empty")]
+    pub descr: Option<String>,
+    #[serde(default)]
+    pub date: Option<Date>,
+    #[serde(default, deserialize_with = "non_empty")]
+    pub category: Option<String>,
+    // В старых tasks.json поля нет
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Переходы между статусами проверяет Workflow, см. List::set_status.
+    // В старых tasks.json вместо статуса is_done
+    #[serde(alias = "is_done")]
+    #[table(string)]
+    pub status: Status,
+    // В старых tasks.json поля нет, такие задачи получают medium
+    #[serde(default)]
+    #[table(string)]
+    pub priority: Priority,
+    // Номер родительской задачи, 0 - задача верхнего уровня
+    #[serde(default)]
+    pub parent: u64,
+    // Номера задач, которые нужно выполнить раньше этой
+    #[serde(default)]
+    pub depends_on: Vec<u64>,
+    // Правило повторения RRULE: выполненная задача порождает следующую, см. List::done
+    #[serde(default)]
+    #[table(string)]
+    pub repeat: Recurrence,
+}
+
+// Вычисляемые столбцы для запросов: is_done из запросов до появления статусов.
+// true - статус done, запись false возвращает выполненную задачу в todo
+pub const DERIVED_KEYS: [(&str, &str); 1] = [("is_done", "bool")];
+
+thread_local! {
+    static LEGACY: Cell<bool> = const { Cell::new(false) };
+}
+
+// Отмечает, что прочитано поле в формате старых tasks.json, см. with_legacy
+pub(crate) fn legacy() {
+    LEGACY.with(|cell| cell.set(true));
+}
+
+// Выполняет загрузку f и сообщает, были ли в ней поля в старом формате
+pub(crate) fn with_legacy<T>(f: impl FnOnce() -> T) -> (T, bool) {
+    let previous = LEGACY.with(|cell| cell.replace(false));
+    let result = f();
+    (result, LEGACY.with(|cell| cell.replace(previous)))
+}
+
+fn non_empty<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.filter(|value| !value.is_empty()))
 }
 
 impl Display for Task {
@@ -19,55 +83,178 @@ impl Display for Task {
         let mut table = prettytable::Table::new();
         table.add_row(Task::get_keys().iter().collect());
 
-        table.add_row(
+        table.add_row(prettytable::Row::new(
             self.get_entries()
                 .iter()
-                .map(|(key, value)| Task::format_by_key(&key, value.to_string()))
+                .map(|(key, value)| Task::cell_by_key(key, value))
                 .collect(),
-        );
+        ));
         write!(f, "{}", table)
     }
 }
 
 impl Task {
+    // Поля, которые задает пользователь: все, кроме id
+    pub fn editable_keys() -> Vec<String> {
+        Task::get_keys()
+            .iter()
+            .filter(|key| *key != "id")
+            .cloned()
+            .collect()
+    }
+
+    // Поля, которые могут быть не заданы (null в tasks.json и SQL)
+    pub fn is_optional(key: &str) -> bool {
+        Task::get_keys()
+            .iter()
+            .zip(Task::get_types())
+            .any(|(field, type_)| field == key && type_.starts_with("Option<"))
+    }
+
+    // Поля и вычисляемые столбцы с типами в порядке вывода
+    pub fn columns() -> Vec<(String, &'static str)> {
+        Task::get_keys()
+            .to_vec()
+            .into_iter()
+            .zip(Task::get_types().iter().copied())
+            .chain(DERIVED_KEYS.map(|(key, type_)| (key.to_string(), type_)))
+            .collect()
+    }
+
+    pub fn has_column(key: &str) -> bool {
+        Task::get_keys().iter().any(|field| field == key)
+            || DERIVED_KEYS.iter().any(|(field, _)| *field == key)
+    }
+
+    // Значение поля или вычисляемого столбца в виде JSON
+    pub fn column_value(&self, key: &str) -> Option<serde_json::Value> {
+        match key {
+            "is_done" => Some(serde_json::Value::Bool(self.status == Status::Done)),
+            key => self.get_value(&key.to_string()),
+        }
+    }
+
+    // Совпадают все поля, кроме id
+    pub fn same_content(&self, other: &Task) -> bool {
+        Task {
+            id: 0,
+            ..self.clone()
+        } == Task {
+            id: 0,
+            ..other.clone()
+        }
+    }
+
     pub fn change_by_key(&mut self, key: &str, value: &str) -> Result<(), String> {
         match key {
             "title" => {
                 self.title = value.to_string();
                 Ok(())
             }
+            // "-" очищает необязательное поле
+            "descr" | "date" | "category" if value == "-" => {
+                match key {
+                    "descr" => self.descr = None,
+                    "date" => self.date = None,
+                    _ => self.category = None,
+                }
+                Ok(())
+            }
             "descr" => {
-                self.descr = value.to_string();
+                self.descr = Some(value.to_string());
                 Ok(())
             }
-            "date" => value
-                .parse()
-                .map(|value| {
-                    self.date = value;
-                })
-                .map_err(|_| format!("Ожидается true или false")),
+            "date" => Date::parse_natural(value).map(|value| {
+                self.date = Some(value);
+            }),
             "category" => {
-                self.category = value.to_string();
+                self.category = Some(value.to_string());
                 Ok(())
             }
-            "is_done" => value
+            "tags" => {
+                self.tags = Task::parse_tags(value);
+                Ok(())
+            }
+            "status" => value.parse().map(|value| {
+                self.status = value;
+            }),
+            "priority" => value.parse().map(|value| {
+                self.priority = value;
+            }),
+            "parent" => value
                 .parse()
                 .map(|value| {
-                    self.is_done = value;
+                    self.parent = value;
+                })
+                .map_err(|_| "Ожидается номер задачи, 0 - без родителя".to_string()),
+            "repeat" => value.parse().map(|value| {
+                self.repeat = value;
+            }),
+            "depends_on" => value
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::parse)
+                .collect::<Result<Vec<_>, _>>()
+                .map(|value| {
+                    self.depends_on = value;
                 })
-                .map_err(|_| format!("Ожидается true или false")),
+                .map_err(|_| "Ожидаются номера задач через запятую".to_string()),
             _ => unreachable!(),
         }
     }
 
+    // Теги через запятую, пустые и повторы отбрасываются
+    pub fn parse_tags(value: &str) -> Vec<String> {
+        let mut tags = Vec::new();
+        Task::add_tags(&mut tags, value.split(','));
+        tags
+    }
+
+    pub fn add_tags<'a>(tags: &mut Vec<String>, new_tags: impl IntoIterator<Item = &'a str>) {
+        for tag in new_tags.into_iter().map(str::trim) {
+            if !tag.is_empty() && !tags.iter().any(|existing| existing == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+
     pub fn format_by_key(key: &str, value: String) -> String {
-        if key == "date" {
-            NaiveDateTime::parse_from_str(&value.trim_matches('"'), "%Y-%m-%dT%H:%M:%S")
-                .ok()
-                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
-                .unwrap_or_else(|| value)
-        } else {
-            value
+        match key {
+            _ if value == "null" => String::new(),
+            "date" => value
+                .trim_matches('"')
+                .parse::<Date>()
+                .map_or(value, |date| date.to_string()),
+            "tags" | "depends_on" => serde_json::from_str::<Vec<serde_json::Value>>(&value)
+                .map(|values| {
+                    values
+                        .iter()
+                        .map(|value| value.as_str().map_or(value.to_string(), str::to_string))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or(value),
+            _ => value,
+        }
+    }
+
+    // Ячейка таблицы: срочные задачи выделяются красным, важные - желтым,
+    // начатые - голубым, выполненные - зеленым
+    pub fn cell_by_key(key: &str, value: &serde_json::Value) -> prettytable::Cell {
+        let cell = prettytable::Cell::new(&Task::format_by_key(key, value.to_string()));
+        match key {
+            "priority" => match serde_json::from_value(value.clone()) {
+                Ok(Priority::Urgent) => cell.style_spec("bFr"),
+                Ok(Priority::High) => cell.style_spec("Fy"),
+                _ => cell,
+            },
+            "status" => match serde_json::from_value(value.clone()) {
+                Ok(Status::InProgress) => cell.style_spec("Fc"),
+                Ok(Status::Done) => cell.style_spec("Fg"),
+                _ => cell,
+            },
+            _ => cell,
         }
     }
 }
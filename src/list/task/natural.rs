@@ -0,0 +1,184 @@
+use chrono::{Datelike, Days, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+use super::Date;
+use crate::clock;
+
+// Срок относительно текущего времени: день без времени или момент
+enum Relative {
+    Day(NaiveDate),
+    Moment(NaiveDateTime),
+}
+
+enum Unit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+const FORMAT: &str = "Ожидается дата: 2024-08-20 12:00 | today | tomorrow 18:00 | next friday \
+                      | in 3 days | end of month | сегодня | завтра | в пятницу | через 2 недели \
+                      | конец месяца";
+
+impl Date {
+    // Дата в свободной записи для add и update: сначала строгие форматы Date,
+    // затем относительные "tomorrow 18:00", "через 2 недели" от clock::now().
+    // Без времени получается задача на весь день
+    pub fn parse_natural(s: &str) -> Result<Date, String> {
+        if let Ok(date) = s.parse() {
+            return Ok(date);
+        }
+
+        let s = s.trim().to_lowercase();
+        let mut words = s.split_whitespace().collect::<Vec<_>>();
+        let time = match words
+            .last()
+            .map(|word| NaiveTime::parse_from_str(word, "%H:%M"))
+        {
+            Some(Ok(time)) => {
+                words.pop();
+                // "at 18:00", "в 18:00"
+                if matches!(words.last(), Some(&"at" | &"в")) {
+                    words.pop();
+                }
+                Some(time)
+            }
+            _ => None,
+        };
+
+        let now = clock::now();
+        let relative = if words.is_empty() && time.is_some() {
+            Relative::Day(now.date())
+        } else {
+            relative(&words, now).ok_or(FORMAT)?
+        };
+        match (relative, time) {
+            (Relative::Day(day), Some(time)) => Ok(day.and_time(time).into()),
+            (Relative::Day(day), None) => Ok(Date::day(day, None)),
+            (Relative::Moment(moment), None) => Ok(moment.into()),
+            (Relative::Moment(_), Some(_)) => Err(FORMAT.into()),
+        }
+    }
+}
+
+fn relative(words: &[&str], now: NaiveDateTime) -> Option<Relative> {
+    let today = now.date();
+    let day = |days: i64| Some(Relative::Day(today + Duration::days(days)));
+    match words {
+        ["today" | "сегодня"] => day(0),
+        ["tomorrow" | "завтра"] => day(1),
+        ["yesterday" | "вчера"] => day(-1),
+        ["послезавтра"] | ["day", "after", "tomorrow"] => day(2),
+        ["end", "of", period] | ["конец", period] | ["в", "конце", period] => {
+            end_of(today, unit(period)?).map(Relative::Day)
+        }
+        ["next", "week" | "month" | "year"] => shift(now, 1, unit(words[1])?),
+        ["in" | "через", count, period] => {
+            let count = match *count {
+                "a" | "an" => 1,
+                count => count.parse().ok()?,
+            };
+            shift(now, count, unit(period)?)
+        }
+        // "через неделю", "через час"
+        ["через", period] => shift(now, 1, unit(period)?),
+        // "friday", "next friday", "в пятницу", "в следующую пятницу"
+        [.., weekday_name] => {
+            let prefix = &words[..words.len() - 1];
+            let is_prefix = prefix.iter().all(|word| {
+                matches!(
+                    *word,
+                    "next"
+                        | "on"
+                        | "this"
+                        | "в"
+                        | "во"
+                        | "следующий"
+                        | "следующую"
+                        | "следующее"
+                        | "следующая"
+                )
+            });
+            if !is_prefix {
+                return None;
+            }
+            let weekday = weekday(weekday_name)?;
+            let days = (7 + weekday.num_days_from_monday() as i64
+                - today.weekday().num_days_from_monday() as i64)
+                % 7;
+            // Ближайший такой день после сегодняшнего
+            day(if days == 0 { 7 } else { days })
+        }
+        [] => None,
+    }
+}
+
+// Сдвиг за пределы дат - None, как и нераспознанная запись
+fn shift(now: NaiveDateTime, count: u32, unit: Unit) -> Option<Relative> {
+    let today = now.date();
+    Some(match unit {
+        Unit::Minute => {
+            Relative::Moment(now.checked_add_signed(Duration::try_minutes(count.into())?)?)
+        }
+        Unit::Hour => Relative::Moment(now.checked_add_signed(Duration::try_hours(count.into())?)?),
+        Unit::Day => Relative::Day(today.checked_add_days(Days::new(count.into()))?),
+        Unit::Week => Relative::Day(today.checked_add_days(Days::new(u64::from(count) * 7))?),
+        Unit::Month => Relative::Day(today.checked_add_months(Months::new(count))?),
+        Unit::Year => Relative::Day(today.checked_add_months(Months::new(count.checked_mul(12)?))?),
+    })
+}
+
+// Последний день недели (воскресенье), месяца или года
+fn end_of(today: NaiveDate, unit: Unit) -> Option<NaiveDate> {
+    match unit {
+        Unit::Week => {
+            Some(today + Duration::days(6 - today.weekday().num_days_from_monday() as i64))
+        }
+        Unit::Month => NaiveDate::from_ymd_opt(today.year(), today.month(), 1)?
+            .checked_add_months(Months::new(1))?
+            .pred_opt(),
+        Unit::Year => NaiveDate::from_ymd_opt(today.year(), 12, 31),
+        _ => None,
+    }
+}
+
+// Единица по началу слова: days, дня, дней, недели, месяцев, лет
+fn unit(word: &str) -> Option<Unit> {
+    let word = word.trim_end_matches('s');
+    Some(match word {
+        "minute" | "min" => Unit::Minute,
+        "hour" => Unit::Hour,
+        "day" => Unit::Day,
+        "week" => Unit::Week,
+        "month" => Unit::Month,
+        "year" => Unit::Year,
+        _ if word.starts_with("мин") => Unit::Minute,
+        _ if word.starts_with("час") => Unit::Hour,
+        _ if word.starts_with("дн") || word.starts_with("ден") => Unit::Day,
+        _ if word.starts_with("нед") => Unit::Week,
+        _ if word.starts_with("мес") => Unit::Month,
+        _ if word.starts_with("год") || word == "лет" => Unit::Year,
+        _ => return None,
+    })
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    let english = word.parse::<Weekday>().ok();
+    let prefixes = [
+        ("пон", Weekday::Mon),
+        ("вт", Weekday::Tue),
+        ("ср", Weekday::Wed),
+        ("чет", Weekday::Thu),
+        ("пят", Weekday::Fri),
+        ("суб", Weekday::Sat),
+        ("вос", Weekday::Sun),
+    ];
+    english.or_else(|| {
+        prefixes
+            .iter()
+            .find(|(prefix, _)| word.starts_with(prefix))
+            .map(|(_, weekday)| *weekday)
+    })
+}
@@ -0,0 +1,258 @@
+use std::{convert::TryFrom, fmt::Display, str::FromStr};
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use super::Date;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+// Подмножество RRULE из RFC 5545: FREQ, INTERVAL, BYDAY (только для WEEKLY), UNTIL, COUNT
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<Weekday>,
+    pub until: Option<Date>,
+    // Сколько повторений осталось, включая текущее
+    pub count: Option<u32>,
+}
+
+// Правило повторения задачи, по умолчанию задача не повторяется.
+// В tasks.json и SQL хранится строкой RRULE, пустая строка - без повторения
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Recurrence(Option<Rule>);
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+const UNTIL_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+// UNTIL с Z - момент в UTC, без Z - местное время пользователя, без времени - весь день
+fn parse_until(value: &str) -> Result<Date, String> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        if let Ok(until) = NaiveDateTime::parse_from_str(utc, UNTIL_FORMAT) {
+            return Ok(DateTime::<Utc>::from_naive_utc_and_offset(until, Utc).into());
+        }
+    }
+    NaiveDateTime::parse_from_str(value, UNTIL_FORMAT)
+        .map(Date::from)
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y%m%d").map(|day| Date::day(day, None)))
+        .or_else(|_| value.parse())
+}
+
+impl Recurrence {
+    pub fn rule(&self) -> Option<&Rule> {
+        self.0.as_ref()
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.0.is_none()
+    }
+
+    // Дата следующего повторения после date и правило для него. None - повторений больше нет.
+    // Считается по местному времени пояса задачи, чтобы 09:00 не сдвигалось при переходе на летнее время
+    pub fn next(&self, date: &Date) -> Option<(Date, Recurrence)> {
+        let rule = self.rule()?;
+        if rule.count.is_some_and(|count| count <= 1) {
+            return None;
+        }
+
+        // Дата за пределами chrono - повторений больше нет
+        let start = date;
+        let date = date.local();
+        let days = |days: i64| date.checked_add_signed(Duration::try_days(days)?);
+        let weeks = || Duration::try_weeks(rule.interval.into());
+        let next = match rule.freq {
+            Frequency::Daily => days(rule.interval.into())?,
+            Frequency::Weekly if rule.by_day.is_empty() => date.checked_add_signed(weeks()?)?,
+            Frequency::Weekly => {
+                let weekday = date.weekday().num_days_from_monday();
+                let offset = |day: &Weekday| day.num_days_from_monday();
+                match rule
+                    .by_day
+                    .iter()
+                    .map(offset)
+                    .filter(|day| *day > weekday)
+                    .min()
+                {
+                    // Следующий день из BYDAY на этой неделе
+                    Some(day) => days((day - weekday).into())?,
+                    // Первый день из BYDAY через INTERVAL недель
+                    None => {
+                        let first = rule.by_day.iter().map(offset).min().unwrap();
+                        let shift = Duration::try_days(i64::from(first) - i64::from(weekday))?;
+                        date.checked_add_signed(weeks()?.checked_add(&shift)?)?
+                    }
+                }
+            }
+            // Месяцы без такого дня (31 число, 29 февраля) пропускаются, как в RFC 5545,
+            // поэтому день месяца не сползает
+            Frequency::Monthly | Frequency::Yearly => {
+                let step = match rule.freq {
+                    Frequency::Yearly => rule.interval.checked_mul(12)?,
+                    _ => rule.interval,
+                };
+                let mut months = step;
+                loop {
+                    let next = date.checked_add_months(Months::new(months))?;
+                    if next.day() == date.day() {
+                        break next;
+                    }
+                    months = months.checked_add(step)?;
+                }
+            }
+        };
+
+        let next = start.with_local(next);
+        if rule
+            .until
+            .as_ref()
+            .is_some_and(|until| next.compare(until).is_gt())
+        {
+            return None;
+        }
+        let rule = Rule {
+            count: rule.count.map(|count| count - 1),
+            ..rule.clone()
+        };
+        Some((next, Recurrence(Some(rule))))
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(rule) = &self.0 else {
+            return Ok(());
+        };
+        let freq = match rule.freq {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={freq}")?;
+        if rule.interval != 1 {
+            write!(f, ";INTERVAL={}", rule.interval)?;
+        }
+        if !rule.by_day.is_empty() {
+            let days = rule
+                .by_day
+                .iter()
+                .map(|day| {
+                    WEEKDAYS
+                        .iter()
+                        .find(|(_, weekday)| weekday == day)
+                        .unwrap()
+                        .0
+                })
+                .collect::<Vec<_>>();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        match &rule.until {
+            Some(until) if until.is_all_day() => {
+                write!(f, ";UNTIL={}", until.datetime().format("%Y%m%d"))?
+            }
+            Some(until) => write!(f, ";UNTIL={}Z", until.start().format(UNTIL_FORMAT))?,
+            None => {}
+        }
+        if let Some(count) = rule.count {
+            write!(f, ";COUNT={count}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s
+            .get(..6)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("RRULE:"))
+            .map_or(s, |_| &s[6..]);
+        if s.is_empty() {
+            return Ok(Recurrence(None));
+        }
+
+        let format = "Ожидается формат: FREQ=DAILY | WEEKLY | MONTHLY | YEARLY[;INTERVAL=n]\
+                      [;BYDAY=MO,..][;UNTIL=YYYYMMDD | ;COUNT=n]";
+        let mut freq = None;
+        let mut rule = Rule {
+            freq: Frequency::Daily,
+            interval: 1,
+            by_day: Vec::new(),
+            until: None,
+            count: None,
+        };
+        for part in s.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or(format)?;
+            let value = value.trim().to_uppercase();
+            match key.trim().to_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(format.into()),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value.parse().ok().filter(|n| *n > 0).ok_or(format)?
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let (_, weekday) = WEEKDAYS
+                            .iter()
+                            .find(|(name, _)| *name == day.trim())
+                            .ok_or(format)?;
+                        if !rule.by_day.contains(weekday) {
+                            rule.by_day.push(*weekday);
+                        }
+                    }
+                }
+                "UNTIL" => rule.until = Some(parse_until(&value)?),
+                "COUNT" => rule.count = Some(value.parse().ok().filter(|n| *n > 0).ok_or(format)?),
+                _ => return Err(format.into()),
+            }
+        }
+
+        rule.freq = freq.ok_or(format)?;
+        if rule.until.is_some() && rule.count.is_some() {
+            return Err("UNTIL и COUNT нельзя указать вместе".into());
+        }
+        if !rule.by_day.is_empty() && rule.freq != Frequency::Weekly {
+            return Err("BYDAY поддерживается только с FREQ=WEEKLY".into());
+        }
+        rule.by_day.sort_by_key(Weekday::num_days_from_monday);
+        Ok(Recurrence(Some(rule)))
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> Self {
+        recurrence.to_string()
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
@@ -0,0 +1,95 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+// Статус задачи, порядок вариантов - порядок сравнения и сортировки.
+// Какие переходы между статусами разрешены, задает Workflow
+#[derive(Clone, Copy, Serialize, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Waiting,
+    Deferred,
+    Done,
+    Cancelled,
+}
+
+impl Status {
+    pub const ALL: [Status; 6] = [
+        Status::Todo,
+        Status::InProgress,
+        Status::Waiting,
+        Status::Deferred,
+        Status::Done,
+        Status::Cancelled,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in_progress",
+            Status::Waiting => "waiting",
+            Status::Deferred => "deferred",
+            Status::Done => "done",
+            Status::Cancelled => "cancelled",
+        }
+    }
+
+    // Выполненная или отмененная задача больше не ждет работы и никого не блокирует
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Status::Done | Status::Cancelled)
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|status| status.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "Ожидается: {}",
+                    Self::ALL.map(|status| status.name()).join(" | ")
+                )
+            })
+    }
+}
+
+// is_done из старых tasks.json: true - done, false - todo
+impl From<bool> for Status {
+    fn from(is_done: bool) -> Self {
+        if is_done {
+            Status::Done
+        } else {
+            Status::Todo
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Stored {
+            Name(String),
+            IsDone(bool),
+        }
+        match Stored::deserialize(deserializer)? {
+            Stored::Name(name) => name.parse().map_err(serde::de::Error::custom),
+            Stored::IsDone(is_done) => {
+                super::legacy();
+                Ok(is_done.into())
+            }
+        }
+    }
+}
@@ -1,9 +1,31 @@
 use super::*;
 use ListError::*;
 
+// Общие данные для тестов запросов
+fn task(title: &str, category: &str, date: &str) -> Task {
+    Task {
+        title: title.into(),
+        category: Some(category.into()),
+        date: Some(date.parse().unwrap()),
+        ..Task::default()
+    }
+}
+
+fn fixture() -> List {
+    List::from(vec![
+        task("Отчет", "Работа", "2023-12-20 12:00"),
+        task("Уборка", "Дом", "2024-08-01 12:00"),
+        task("Созвон", "Работа", "2024-08-02 12:00"),
+    ])
+}
+
+fn titles(list: &List) -> Vec<&str> {
+    list.iter().map(|task| task.title.as_str()).collect()
+}
+
 #[test]
 fn not_exist_task() {
-    let mut list = List(vec![]);
+    let mut list = List::from(vec![]);
     let title = "title".to_string();
 
     assert_eq!(list.done(title.clone()), Err(TaskNotExist(title.clone())));
@@ -22,13 +44,13 @@ fn not_exist_task() {
 #[test]
 fn done() {
     // is_done: false
-    let mut list = List(vec![Task::default()]);
+    let mut list = List::from(vec![Task::default()]);
 
     // Завршаем задачу
     assert_eq!(
         list.done(String::default()),
         Ok(&Task {
-            is_done: true,
+            status: Status::Done,
             ..Task::default()
         })
     );
@@ -39,7 +61,7 @@ fn done() {
 #[test]
 fn update() {
     let mut task = Task::default();
-    let mut list = List(vec![Task::default()]);
+    let mut list = List::from(vec![Task::default()]);
 
     // Обновляем задачу, не изменив поля -> Ошибка
     assert_eq!(
@@ -48,11 +70,11 @@ fn update() {
     );
 
     // Обновляем задачу изменив одно поле
-    task.is_done = true;
+    task.status = Status::Done;
     assert_eq!(
         list.update(String::default(), &task.clone()),
         Ok(&Task {
-            is_done: true,
+            status: Status::Done,
             ..task
         })
     );
@@ -60,23 +82,167 @@ fn update() {
 
 #[test]
 fn delete() {
-    let mut list = List(vec![Task::default()]);
+    let mut list = List::from(vec![Task::default()]);
     assert_eq!(list.delete(String::default()), Ok(Task::default()));
 }
 
+#[test]
+fn ids() {
+    let task = |title: &str, date: &str| Task {
+        title: title.into(),
+        date: Some(date.parse().unwrap()),
+        ..Task::default()
+    };
+    // Старый tasks.json без id: номера выдаются при загрузке, повторные заменяются
+    let mut list = serde_json::from_str::<List>(
+        r#"[
+            {"title": "Отчет", "descr": "", "date": "2024-08-20T12:00:00", "category": "", "is_done": false},
+            {"id": 5, "title": "Отчет", "descr": "", "date": "2024-08-21T12:00:00", "category": "", "is_done": false},
+            {"id": 5, "title": "3", "descr": "", "date": "2024-08-22T12:00:00", "category": "", "is_done": false}
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        list.iter().map(|task| task.id).collect::<Vec<_>>(),
+        vec![6, 5, 7]
+    );
+
+    // Одинаковые названия с разными датами - разные задачи
+    assert_eq!(
+        list.add(task("Отчет", "2024-08-23 12:00"))
+            .map(|task| task.id),
+        Ok(8)
+    );
+    assert_eq!(
+        list.add(task("Отчет", "2024-08-23 12:00")),
+        Err(TaskAlreadyExists)
+    );
+    assert_eq!(
+        list.done("Отчет".into()),
+        Err(AmbiguousTitle("Отчет".into(), vec![6, 5, 8]))
+    );
+
+    // Число - id или название
+    assert_eq!(
+        list.done("5".into()).map(|task| task.status),
+        Ok(Status::Done)
+    );
+    assert_eq!(list.get_task("3").map(|task| task.id), Ok(7));
+    assert_eq!(list.get_task("7").map(|task| task.title.as_str()), Ok("3"));
+
+    // update сохраняет id
+    assert_eq!(
+        list.update("8".into(), &task("Отчет за год", "2024-08-23 12:00"))
+            .map(|task| task.id),
+        Ok(8)
+    );
+    assert_eq!(list.delete("6".into()).map(|task| task.id), Ok(6));
+    assert_eq!(list.get_task("Отчет").map(|task| task.id), Ok(5));
+    assert_eq!(
+        list.select("select * where id >= 7").map(|list| list.len()),
+        Ok(2)
+    );
+    assert_eq!(
+        list.select("select title where id in (5, 8, 100)")
+            .map(|list| list.iter().map(|task| task.id).collect::<Vec<_>>()),
+        Ok(vec![5, 8])
+    );
+
+    // Номер удаленной задачи не выдается снова
+    assert_eq!(
+        list.add(task("Новая", "2024-08-23 12:00"))
+            .map(|task| task.id),
+        Ok(9)
+    );
+
+    // Число совпадает с id одной задачи и названием другой
+    list.add(task("8", "2024-08-24 12:00")).unwrap();
+    assert_eq!(
+        list.done("8".into()),
+        Err(AmbiguousTitle("8".into(), vec![8, 10]))
+    );
+    assert_eq!(list.get_task("10").map(|task| task.title.as_str()), Ok("8"));
+
+    // ...и после сохранения и загрузки
+    list.delete("10".into()).unwrap();
+    let json = serde_json::to_string(&list).unwrap();
+    let mut list = serde_json::from_str::<List>(&json).unwrap();
+    assert_eq!(
+        list.add(task("После загрузки", "2024-08-25 12:00"))
+            .map(|task| task.id),
+        Ok(11)
+    );
+
+    // Сохраненный номер меньше занятых - берется следующий после задач
+    let mut list = serde_json::from_str::<List>(
+        r#"{"next_id": 1, "tasks": [{"id": 3, "title": "a", "status": "todo"}]}"#,
+    )
+    .unwrap();
+    assert_eq!(list.add(Task::default()).map(|task| task.id), Ok(4));
+}
+
+#[test]
+fn migration() {
+    let load = |json: &str| serde_json::from_str::<List>(json).unwrap();
+    let task = r#""id": 1, "title": "a", "date": "2024-08-20T12:00:00Z", "status": "todo""#;
+
+    // Сохраненный в новом формате файл перезаписывать не нужно
+    let list = load(&format!(r#"{{"next_id": 2, "tasks": [{{{task}}}]}}"#));
+    assert!(!list.is_migrated());
+    let json = serde_json::to_string(&list).unwrap();
+    assert!(!load(&json).is_migrated());
+
+    // Массив задач, выданные номера, даты без смещения и is_done - старый формат
+    for tasks in [
+        format!("[{{{task}}}]"),
+        r#"{"next_id": 2, "tasks": [{"title": "a", "status": "todo"}]}"#.into(),
+        r#"{"next_id": 2, "tasks": [{"id": 1, "title": "a", "date": "2024-08-20T12:00:00", "status": "todo"}]}"#.into(),
+        r#"{"next_id": 2, "tasks": [{"id": 1, "title": "a", "is_done": true}]}"#.into(),
+    ] {
+        assert!(load(&tasks).is_migrated(), "{}", tasks);
+    }
+
+    // Отметка не переходит на следующую загрузку
+    assert!(!load(&json).is_migrated());
+}
+
 #[cfg(test)]
 mod select {
     use super::*;
 
     #[test]
     fn not_valid_query() {
-        let list = List(vec![Task::default(), Task::default()]);
+        let list = List::from(vec![Task::default(), Task::default()]);
 
-        assert_eq!(list.select("select *"), Ok(List(list.clone())));
+        assert_eq!(list.select("select *"), Ok(list.clone()));
+
+        // Синтаксические ошибки с позицией
+        assert_eq!(
+            list.select("select * where"),
+            Err(Sql(Syntax {
+                message: "Expected: an expression:, found: EOF".into(),
+                line: 1,
+                column: 15
+            }))
+        );
+        assert_eq!(
+            list.select("* where"),
+            Err(Sql(Syntax {
+                message: "Expected: an SQL statement, found: *".into(),
+                line: 1,
+                column: 1
+            }))
+        );
 
         // NotValidQuery
-        assert_eq!(list.select("select * where"), Err(Sql(NotValidQuery)));
-        assert_eq!(list.select("* where"), Err(Sql(NotValidQuery)));
+        assert_eq!(
+            list.select("insert into tasks values ('a')"),
+            Err(Sql(NotValidQuery))
+        );
+        assert_eq!(
+            list.select("select * where title"),
+            Err(Sql(UnhandledCondition("title".into())))
+        );
 
         assert_eq!(
             list.select("select * where name = ''"),
@@ -86,42 +252,40 @@ mod select {
 
     #[test]
     fn select_by_one_field() {
-        let list = List(vec![Task::default(), Task::default()]);
+        let task = Task {
+            date: Some(Date::default()),
+            ..Task::default()
+        };
+        let list = List::from(vec![task.clone(), task]);
 
         // String
 
         // both quotes: "", ''
-        assert_eq!(
-            list.select("select * where title = ''"),
-            Ok(List(list.clone()))
-        );
-        assert_eq!(
-            list.select("select * where title = \"\""),
-            Ok(List(list.clone()))
-        );
+        assert_eq!(list.select("select * where title = ''"), Ok(list.clone()));
+        assert_eq!(list.select("select * where title = \"\""), Ok(list.clone()));
 
         // Проверка оператора
         assert_eq!(
             list.select("select * where title || ''"),
-            Err(Sql(UnhandledOperator(StringConcat)))
+            Err(Sql(UnhandledOperator("||".into())))
         );
 
         assert_eq!(
             list.select("select * where 22 = ''"),
-            Err(Sql(Format("WHERE [Identifier ..]".into())))
+            Err(Sql(SqlError::format("WHERE [Identifier ..]", "22")))
         );
 
         assert_eq!(
             list.select("select * where title > \"\""),
-            Err(Sql(Format("title = ..".into())))
+            Err(Sql(SqlError::format("title = ..", ">")))
         );
         assert_eq!(
             list.select("select * where title = 22"),
-            Err(Sql(Format("[.. StringValue]".into())))
+            Err(Sql(SqlError::format("[.. StringValue]", "22")))
         );
         assert_eq!(
             list.select("select * where title = title"),
-            Err(Sql(Format("[.. StringValue]".into())))
+            Err(Sql(SqlError::format("[.. StringValue]", "title")))
         );
 
         // Like
@@ -129,118 +293,2751 @@ mod select {
         // both quotes: "", ''
         assert_eq!(
             list.select("select * where title like 'tit'"),
-            Ok(List(vec![]))
+            Ok(List::from(vec![]))
         );
         assert_eq!(
             list.select("select * where title like \"tit\""),
-            Ok(List(vec![]))
+            Ok(List::from(vec![]))
         );
 
         assert_eq!(
             list.select("select * where date like 22"),
-            Err(Sql(Format("[StringIdnetifier like ..]".into())))
+            Err(Sql(SqlError::format("[StringIdnetifier like ..]", "date")))
         );
 
         assert_eq!(
             list.select("select * where title like 22"),
-            Err(Sql(Format("[.. like StringValue]".into())))
+            Err(Sql(SqlError::format("[.. like StringValue]", "22")))
         );
 
         assert_eq!(
             list.select("select * where 22 like 22"),
-            Err(Sql(Format("[StringIdnetifier like ..]".into())))
+            Err(Sql(SqlError::format("[StringIdnetifier like ..]", "22")))
         );
 
         // Bool
         assert_eq!(
             list.select("select * where is_done = false"),
-            Ok(List(list.clone()))
+            Ok(list.clone())
         );
         assert_eq!(
             list.select("select * where is_done = 'false'"),
-            Err(Sql(Format("[.. true | false]".into())))
+            Err(Sql(SqlError::format("[.. true | false]", "'false'")))
         );
         assert_eq!(
             list.select("select * where is_done > false"),
-            Err(Sql(Format("is_done = ..".into())))
+            Err(Sql(SqlError::format("is_done = ..", ">")))
         );
 
         // Date -> operateor Eq
         assert_eq!(
             list.select("select * where date = \"1970-01-01 00:00\""),
-            Ok(List(list.clone()))
+            Ok(list.clone())
         );
         assert_eq!(
             list.select("select * where date = \"1970-01-01T00:00:00\""),
-            Ok(List(list.clone()))
+            Ok(list.clone())
         );
 
         assert_eq!(
             list.select("select * where date = false"),
-            Err(Sql(Format("[.. \"Date\"]".into())))
+            Err(Sql(SqlError::format("[.. \"Date\"]", "false")))
         );
         assert_eq!(
             list.select("select * where date = 'false'"),
-            Err(Sql(Format("[.. \"Date\"]".into())))
+            Err(Sql(SqlError::format("[.. \"Date\"]", "'false'")))
         );
         assert_eq!(
             list.select("select * where date > false"),
-            Err(Sql(Format("[.. \"Date\"]".into())))
+            Err(Sql(SqlError::format("[.. \"Date\"]", "false")))
         );
 
         // Date -> compares operateors
         assert_eq!(
             list.select("select * where date < \"1970-01-01 00:01\""),
-            Ok(List(list.clone()))
+            Ok(list.clone())
         );
         assert_eq!(
             list.select("select * where date > \"1970-01-01 00:01\""),
-            Ok(List(vec![]))
+            Ok(List::from(vec![]))
         );
         assert_eq!(
             list.select("select * where date <= \"1970-01-01 00:00\""),
-            Ok(List(list.clone()))
+            Ok(list.clone())
         );
         assert_eq!(
             list.select("select * where date < \"1970-01-01 00:00\""),
-            Ok(List(vec![]))
+            Ok(List::from(vec![]))
         );
     }
 
     #[test]
     fn select_by_combinations() {
-        let list = List(vec![Task::default(), Task::default()]);
+        let task = Task {
+            date: Some(Date::default()),
+            ..Task::default()
+        };
+        let list = List::from(vec![task.clone(), task]);
 
-        // Условие Or оператора -> Ошибка
+        // Оператор Or
         assert_eq!(
             list.select("select * where title = '' or title like ''"),
-            Err(Sql(UnhandledOperator(Or)))
+            Ok(list.clone())
         );
 
         // Оператор And
 
         assert_eq!(
             list.select("select * where title = '' and title like '' and date = \"1970-01-01 00:00\" and date <= \"1970-01-01 00:00\""),
-            Ok(List(list.clone()))
+            Ok(list.clone())
         );
 
         assert_eq!(
             list.select(
                 "select * where title = 'name' and title like '' and date = \"1970-01-01 00:00\""
             ),
-            Ok(List(vec![]))
+            Ok(List::from(vec![]))
         );
         assert_eq!(
             list.select(
                 "select * where title = '' and title like 'na' and date = \"1970-01-01 00:00\""
             ),
-            Ok(List(vec![]))
+            Ok(List::from(vec![]))
         );
         assert_eq!(
             list.select(
                 "select * where title = '' and title like 'na' and date > \"1970-01-01 00:00\""
             ),
-            Ok(List(vec![]))
+            Ok(List::from(vec![]))
+        );
+    }
+
+    #[test]
+    fn select_by_bool_logic() {
+        let work = Task {
+            title: "Отчет".into(),
+            category: Some("Работа".into()),
+            ..Task::default()
+        };
+        let home = Task {
+            title: "Уборка".into(),
+            category: Some("Дом".into()),
+            status: Status::Done,
+            ..Task::default()
+        };
+        let other = Task {
+            title: "Прогулка".into(),
+            category: Some("Личное".into()),
+            ..Task::default()
+        };
+        let list = List::from(vec![work.clone(), home.clone(), other.clone()]);
+
+        assert_eq!(
+            list.select("select * where category = 'Работа' or category = 'Дом'"),
+            Ok(List::from(vec![work.clone(), home.clone()]))
+        );
+
+        assert_eq!(
+            list.select("select * where not is_done = true"),
+            Ok(List::from(vec![work.clone(), other.clone()]))
+        );
+
+        // And связывает сильнее, чем Or
+        assert_eq!(
+            list.select(
+                "select * where category = 'Дом' or category = 'Работа' and is_done = true"
+            ),
+            Ok(List::from(vec![home.clone()]))
+        );
+        assert_eq!(
+            list.select(
+                "select * where (category = 'Дом' or category = 'Работа') and is_done = false"
+            ),
+            Ok(List::from(vec![work.clone()]))
+        );
+
+        // Not связывает сильнее, чем And
+        assert_eq!(
+            list.select("select * where not category = 'Дом' and not title = 'Отчет'"),
+            Ok(List::from(vec![other.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where not (category = 'Дом' or title like 'Отч%')"),
+            Ok(List::from(vec![other]))
+        );
+
+        // Ошибка внутри Or/Not обнаруживается даже без подходящих задач
+        assert_eq!(
+            List::from(vec![]).select("select * where not (title = '' or name = '')"),
+            Err(Sql(NonExistentField("name".into())))
+        );
+    }
+
+    #[test]
+    fn priority() {
+        let task = |title: &str, priority| Task {
+            title: title.into(),
+            priority,
+            ..Task::default()
+        };
+        let low = task("low", Priority::Low);
+        let urgent = task("urgent", Priority::Urgent);
+        let high = task("high", Priority::High);
+        let list = List::from(vec![low.clone(), urgent.clone(), high.clone()]);
+
+        assert_eq!(
+            list.select("select * where priority >= 'high'"),
+            Ok(List::from(vec![urgent.clone(), high.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where priority < 'MEDIUM'"),
+            Ok(List::from(vec![low.clone()]))
+        );
+        assert_eq!(
+            list.select("select * order by priority desc"),
+            Ok(List::from(vec![urgent.clone(), high.clone(), low.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where priority = 'later'"),
+            Err(Sql(SqlError::format(
+                "[.. 'low' | 'medium' | 'high' | 'urgent']",
+                "'later'"
+            )))
+        );
+        // Строковый параметр приводится к приоритету
+        assert_eq!(
+            List::prepare("select * where priority = ?")
+                .and_then(|query| query.select(&list, &["urgent".into()])),
+            Ok(List::from(vec![urgent]))
+        );
+    }
+
+    #[test]
+    fn tags() {
+        let task = |title: &str, tags: &[&str]| Task {
+            title: title.into(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Task::default()
+        };
+        let report = task("Отчет", &["работа", "срочно"]);
+        let cleaning = task("Уборка", &["дом"]);
+        let call = task("Звонок", &["работа"]);
+        let list = List::from(vec![report.clone(), cleaning.clone(), call.clone()]);
+
+        assert_eq!(
+            list.select("select * where 'работа' in tags"),
+            Ok(List::from(vec![report.clone(), call.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where tags contains 'срочно' or 'дом' = any(tags)"),
+            Ok(List::from(vec![report.clone(), cleaning.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where 'работа' not in tags"),
+            Ok(List::from(vec![cleaning.clone()]))
+        );
+        // Обычный IN со списком не затронут
+        assert_eq!(
+            list.select("select * where title in ('Уборка')"),
+            Ok(List::from(vec![cleaning.clone()]))
+        );
+        assert_eq!(
+            List::prepare("select * where ? in tags")
+                .and_then(|query| query.select(&list, &["дом".into()])),
+            Ok(List::from(vec![cleaning]))
+        );
+        assert_eq!(
+            list.select("select * where tags = 'дом'"),
+            Err(Sql(SqlError::format(
+                "[.. IN tags | tags CONTAINS ..]",
+                "tags ="
+            )))
+        );
+        assert_eq!(
+            list.select("select * where 'дом' in category"),
+            Err(Sql(SqlError::format(
+                "[.. IN tags | depends_on]",
+                "category"
+            )))
+        );
+    }
+
+    #[test]
+    fn order_by_and_limit() {
+        let task = |title: &str, date: &str, is_done: bool| Task {
+            title: title.into(),
+            date: Some(date.parse().unwrap()),
+            status: is_done.into(),
+            ..Task::default()
+        };
+        let a = task("a", "2024-08-20 12:00", true);
+        let b = task("b", "2024-08-19 12:00", false);
+        let c = task("c", "2024-08-21 12:00", false);
+        let d = task("d", "2024-08-19 12:00", true);
+        let list = List::from(vec![a.clone(), b.clone(), c.clone(), d.clone()]);
+
+        assert_eq!(
+            list.select("select * order by date"),
+            Ok(List::from(vec![b.clone(), d.clone(), a.clone(), c.clone()]))
+        );
+        assert_eq!(
+            list.select("select * order by title desc"),
+            Ok(List::from(vec![d.clone(), c.clone(), b.clone(), a.clone()]))
+        );
+
+        // Несколько ключей
+        assert_eq!(
+            list.select("select * order by is_done desc, date asc"),
+            Ok(List::from(vec![d.clone(), a.clone(), b.clone(), c.clone()]))
+        );
+
+        // Совместно с WHERE, LIMIT и OFFSET
+        assert_eq!(
+            list.select("select * where is_done = false order by date desc limit 1"),
+            Ok(List::from(vec![c.clone()]))
+        );
+        assert_eq!(
+            list.select("select * order by date limit 2 offset 1"),
+            Ok(List::from(vec![d.clone(), a.clone()]))
+        );
+        assert_eq!(
+            list.select("select * limit 10 offset 3"),
+            Ok(List::from(vec![d.clone()]))
+        );
+        assert_eq!(list.select("select * limit 0"), Ok(List::from(vec![])));
+
+        // Ошибки
+        assert_eq!(
+            list.select("select * order by name"),
+            Err(Sql(NonExistentField("name".into())))
+        );
+        assert_eq!(
+            list.select("select * order by length(title)"),
+            Err(Sql(UnhandledOrderBy("length(title)".into())))
+        );
+        assert_eq!(
+            list.select("select * limit 'a'"),
+            Err(Sql(SqlError::format("LIMIT [Number]", "'a'")))
+        );
+        assert_eq!(
+            list.select("select * limit 1 offset -1"),
+            Err(Sql(SqlError::format("OFFSET [Number]", "-1")))
+        );
+    }
+
+    #[test]
+    fn projection() {
+        use rows::Column;
+        use serde_json::json;
+
+        let task = Task {
+            title: "Отчет".into(),
+            date: Some("2024-08-20 12:00".parse().unwrap()),
+            ..Task::default()
+        };
+        let list = List::from(vec![task.clone(), Task::default()]);
+
+        let rows = list
+            .query("select title, date as deadline where title = 'Отчет'")
+            .unwrap();
+        assert_eq!(
+            rows.columns,
+            vec![
+                Column::new("title"),
+                Column {
+                    name: "deadline".into(),
+                    ..Column::new("date")
+                }
+            ]
+        );
+        assert_eq!(
+            rows.rows,
+            vec![vec![json!("Отчет"), json!("2024-08-20T12:00:00Z")]]
+        );
+
+        // Псевдоним в заголовке и дата в формате вывода
+        let rendered = rows.to_string();
+        assert!(rendered.contains("deadline") && !rendered.contains("descr"));
+        assert!(rendered.contains("2024-08-20 12:00"));
+
+        // * раскрывается во все ключи Table
+        let rows = list.query("select *, title t").unwrap();
+        assert_eq!(rows.columns.len(), Task::get_keys().len() + 1);
+        assert_eq!(rows.rows.len(), 2);
+
+        // select по-прежнему возвращает задачи целиком
+        assert_eq!(
+            list.select("select title where title = 'Отчет'"),
+            Ok(List::from(vec![task]))
+        );
+
+        assert_eq!(
+            list.query("select name"),
+            Err(Sql(NonExistentField("name".into())))
+        );
+        assert_eq!(
+            list.query("select upper(title)"),
+            Err(Sql(UnhandledSelectItem("upper(title)".into())))
+        );
+        assert_eq!(
+            list.query("select tasks.*"),
+            Err(Sql(UnhandledSelectItem("tasks.*".into())))
+        );
+    }
+
+    #[test]
+    fn aggregates() {
+        use serde_json::json;
+
+        let task = |category: &str, date: &str, is_done: bool| Task {
+            category: Some(category.into()),
+            date: Some(date.parse().unwrap()),
+            status: is_done.into(),
+            ..Task::default()
+        };
+        let list = List::from(vec![
+            task("Работа", "2024-08-20 12:00", false),
+            task("Личное", "2024-08-19 12:00", false),
+            task("Работа", "2024-08-18 12:00", false),
+            task("Работа", "2024-08-21 12:00", true),
+        ]);
+
+        let rows = list
+            .query("select category, count(*) where is_done = false group by category")
+            .unwrap();
+        assert_eq!(
+            rows.columns.iter().map(|c| &c.name).collect::<Vec<_>>(),
+            vec!["category", "count(*)"]
+        );
+        assert_eq!(
+            rows.rows,
+            vec![
+                vec![json!("Работа"), json!(2)],
+                vec![json!("Личное"), json!(1)]
+            ]
+        );
+
+        // Порядок групп следует ORDER BY, LIMIT применяется к группам
+        assert_eq!(
+            list.query(
+                "select category, max(date) as last group by category order by category limit 1"
+            )
+            .unwrap()
+            .rows,
+            vec![vec![json!("Личное"), json!("2024-08-19T12:00:00Z")]]
+        );
+
+        // Без GROUP BY - одна строка на всю выборку
+        let rows = list
+            .query("select min(date), max(date), count(title)")
+            .unwrap();
+        assert_eq!(
+            rows.rows,
+            vec![vec![
+                json!("2024-08-18T12:00:00Z"),
+                json!("2024-08-21T12:00:00Z"),
+                json!(4)
+            ]]
+        );
+        assert!(rows.to_string().contains("2024-08-18 12:00"));
+
+        assert_eq!(
+            List::from(vec![])
+                .query("select count(*), min(date)")
+                .unwrap()
+                .rows,
+            vec![vec![json!(0), serde_json::Value::Null]]
+        );
+        assert_eq!(
+            List::from(vec![])
+                .query("select category, count(*) group by category")
+                .unwrap()
+                .rows,
+            Vec::<Vec<serde_json::Value>>::new()
+        );
+
+        // Ошибки
+        assert_eq!(
+            list.query("select title, count(*) group by category"),
+            Err(Sql(NotGrouped("title".into())))
+        );
+        assert_eq!(
+            list.query("select * group by category"),
+            Err(Sql(NotGrouped("id".into())))
+        );
+        assert_eq!(
+            list.query("select sum(date)"),
+            Err(Sql(UnhandledSelectItem("sum(date)".into())))
+        );
+        assert_eq!(
+            list.query("select min(*)"),
+            Err(Sql(UnhandledSelectItem("min(*)".into())))
+        );
+        assert_eq!(
+            list.query("select count(name)"),
+            Err(Sql(NonExistentField("name".into())))
+        );
+        assert_eq!(
+            list.query("select category group by name"),
+            Err(Sql(NonExistentField("name".into())))
+        );
+    }
+
+    #[test]
+    fn predicates() {
+        let a = task("Отчет", "Работа", "2024-07-31 12:00");
+        let b = task("Уборка", "Дом", "2024-08-01 00:00");
+        let c = task("Прогулка", "Личное", "2024-08-31 00:00");
+        let list = List::from(vec![a.clone(), b.clone(), c.clone()]);
+
+        // IN
+        assert_eq!(
+            list.select("select * where category in ('Работа', \"Дом\")"),
+            Ok(List::from(vec![a.clone(), b.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where category not in ('Работа', 'Дом')"),
+            Ok(List::from(vec![c.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where is_done in (true)"),
+            Ok(List::from(vec![]))
+        );
+
+        // BETWEEN - границы включаются
+        assert_eq!(
+            list.select(
+                "select * where date between \"2024-08-01 00:00\" and \"2024-08-31 00:00\""
+            ),
+            Ok(List::from(vec![b.clone(), c.clone()]))
+        );
+        assert_eq!(
+            list.select(
+                "select * where date not between \"2024-08-01 00:00\" and \"2024-08-31 00:00\""
+            ),
+            Ok(List::from(vec![a.clone()]))
+        );
+
+        // NOT LIKE
+        assert_eq!(
+            list.select("select * where title not like '%ка'"),
+            Ok(List::from(vec![a.clone()]))
+        );
+
+        // Описание не задано ни у одной задачи
+        assert_eq!(
+            list.select("select * where descr is null"),
+            Ok(list.clone())
+        );
+        assert_eq!(
+            list.select("select * where descr is not null"),
+            Ok(List::from(vec![]))
+        );
+
+        // Проверка типов
+        assert_eq!(
+            list.select("select * where category in ('Работа', 1)"),
+            Err(Sql(SqlError::format("[.. StringValue]", "1")))
+        );
+        assert_eq!(
+            list.select("select * where date in ('2024-08-32')"),
+            Err(Sql(SqlError::format("[.. \"Date\"]", "'2024-08-32'")))
+        );
+        assert_eq!(
+            list.select("select * where title between 'a' and 'b'"),
+            Err(Sql(SqlError::format("title = ..", ">=")))
+        );
+        assert_eq!(
+            list.select("select * where date between \"2024-08-01 00:00\" and false"),
+            Err(Sql(SqlError::format("[.. \"Date\"]", "false")))
+        );
+        assert_eq!(
+            list.select("select * where name is null"),
+            Err(Sql(NonExistentField("name".into())))
+        );
+        assert_eq!(
+            list.select("select * where name in ('a')"),
+            Err(Sql(NonExistentField("name".into())))
+        );
+    }
+
+    #[test]
+    fn like_patterns() {
+        let task = |title: &str| Task {
+            title: title.into(),
+            ..Task::default()
+        };
+        let buy = task("Buy milk");
+        let report = task("Отчет 50%");
+        let call = task("Звонок клиенту");
+        let list = List::from(vec![buy.clone(), report.clone(), call.clone()]);
+
+        // %, _ и привязка к началу и концу строки
+        assert_eq!(
+            list.select("select * where title like 'Buy%'"),
+            Ok(List::from(vec![buy.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where title like 'milk'"),
+            Ok(List::from(vec![]))
+        );
+        assert_eq!(
+            list.select("select * where title like '_uy m_lk'"),
+            Ok(List::from(vec![buy.clone()]))
+        );
+
+        // ESCAPE
+        assert_eq!(
+            list.select("select * where title like '%!%' escape '!'"),
+            Ok(List::from(vec![report.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where title like '%.%'"),
+            Ok(List::from(vec![]))
+        );
+
+        // ILIKE с кириллицей
+        assert_eq!(
+            list.select("select * where title ilike 'ЗВОНОК%'"),
+            Ok(List::from(vec![call.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where title not ilike '%КЛИЕНТУ'"),
+            Ok(List::from(vec![buy.clone(), report.clone()]))
+        );
+
+        // REGEXP и ~
+        assert_eq!(
+            list.select("select * where title regexp '[0-9]+%$'"),
+            Ok(List::from(vec![report.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where title ~ '^Buy'"),
+            Ok(List::from(vec![buy.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where title ~* 'ОТЧЕТ|звонок'"),
+            Ok(List::from(vec![report.clone(), call.clone()]))
+        );
+        assert_eq!(
+            list.select("select * where title !~ 'milk'"),
+            Ok(List::from(vec![report, call]))
+        );
+
+        // Ошибки
+        assert!(matches!(
+            list.select("select * where title ~ '('"),
+            Err(Sql(InvalidRegex(_)))
+        ));
+        assert_eq!(
+            list.select("select * where title like 'a' escape '!!'"),
+            Err(Sql(SqlError::format("[.. ESCAPE Char]", "'!!'")))
+        );
+        assert_eq!(
+            list.select("select * where title like 'a!' escape '!'"),
+            Err(Sql(SqlError::format("[.. ESCAPE Char Char]", "'a!'")))
+        );
+        assert_eq!(
+            list.select("select * where date ilike 'a'"),
+            Err(Sql(SqlError::format("[StringIdnetifier ilike ..]", "date")))
+        );
+        assert_eq!(
+            list.select("select * where is_done ~ 'a'"),
+            Err(Sql(SqlError::format("[StringIdnetifier ~ ..]", "is_done")))
+        );
+        assert_eq!(
+            list.select("select * where title regexp 1"),
+            Err(Sql(SqlError::format("[.. regexp StringValue]", "1")))
+        );
+    }
+
+    #[test]
+    fn date_functions() {
+        use crate::clock;
+
+        let task = |title: &str, date: &str| Task {
+            title: title.into(),
+            date: Some(date.parse().unwrap()),
+            ..Task::default()
+        };
+        // 2024-08-20 - вторник
+        let overdue = task("overdue", "2024-08-19 18:00");
+        let today = task("today", "2024-08-20 18:00");
+        let soon = task("soon", "2024-08-23 09:00");
+        let later = task("later", "2024-09-25 09:00");
+        let list = List::from(vec![
+            overdue.clone(),
+            today.clone(),
+            soon.clone(),
+            later.clone(),
+        ]);
+
+        let select = |sql: &str| {
+            clock::with_now(
+                "2024-08-20 12:00".parse::<Date>().unwrap().datetime(),
+                || list.select(sql),
+            )
+        };
+
+        assert_eq!(
+            select("select * where date < NOW()"),
+            Ok(List::from(vec![overdue.clone()]))
+        );
+        assert_eq!(
+            select("select * where date > now() and date <= NOW() + INTERVAL '3 days'"),
+            Ok(List::from(vec![today.clone(), soon.clone()]))
+        );
+        assert_eq!(
+            select("select * where date > current_timestamp - interval '1' day and date < current_timestamp"),
+            Ok(List::from(vec![overdue.clone()]))
+        );
+        assert_eq!(
+            select("select * where date > NOW() + INTERVAL '1 month'"),
+            Ok(List::from(vec![later.clone()]))
+        );
+        assert_eq!(
+            select("select * where date between now() and now() + interval '1 week 1 day'"),
+            Ok(List::from(vec![today.clone(), soon.clone()]))
+        );
+
+        // DATE() и CURRENT_DATE
+        assert_eq!(
+            select("select * where DATE(date) = CURRENT_DATE"),
+            Ok(List::from(vec![today.clone()]))
+        );
+        assert_eq!(
+            select("select * where date(date) = date(\"2024-08-23 23:59\")"),
+            Ok(List::from(vec![soon.clone()]))
+        );
+
+        // EXTRACT
+        assert_eq!(
+            select("select * where EXTRACT(DOW FROM date) = 5"),
+            Ok(List::from(vec![soon.clone()]))
+        );
+        assert_eq!(
+            select("select * where extract(isodow from date) in (1, 2)"),
+            Ok(List::from(vec![overdue.clone(), today.clone()]))
+        );
+        assert_eq!(
+            select("select * where extract(month from date) >= 9"),
+            Ok(List::from(vec![later.clone()]))
+        );
+
+        // Без with_now используется текущее время
+        assert_eq!(
+            list.select("select * where date > now()"),
+            Ok(List::from(vec![]))
+        );
+
+        // Ошибки
+        assert_eq!(
+            select("select * where date < now() + 3"),
+            Err(Sql(SqlError::format(
+                "[.. \"Date\" +|- INTERVAL 'count unit']",
+                "3"
+            )))
+        );
+        assert_eq!(
+            select("select * where date < now() + interval '3 fortnights'"),
+            Err(Sql(SqlError::format(
+                "INTERVAL 'count unit'",
+                "INTERVAL '3 fortnights'"
+            )))
+        );
+        for interval in [
+            "400000000 years",
+            "4000000000 months 4000000000 months",
+            "4000000000 weeks 4000000000 weeks 4000000000 weeks 4000000000 weeks",
+        ] {
+            assert_eq!(
+                select(&format!(
+                    "select * where date < now() + interval '{interval}'"
+                )),
+                Err(Sql(SqlError::format(
+                    "INTERVAL 'count unit'",
+                    format!("INTERVAL '{interval}'")
+                )))
+            );
+        }
+        // Интервал допустим, но дата выходит за пределы
+        assert_eq!(
+            select("select * where date < now() + interval '4000000000 weeks'"),
+            Err(Sql(SqlError::format(
+                "[.. \"Date\"]",
+                "now() + INTERVAL '4000000000 weeks'"
+            )))
+        );
+        assert_eq!(
+            select("select * where date < tomorrow()"),
+            Err(Sql(UnhandledFunction("tomorrow()".into())))
+        );
+        assert_eq!(
+            select("select * where extract(dow from date) = 'friday'"),
+            Err(Sql(SqlError::format("[.. Number]", "'friday'")))
+        );
+        assert_eq!(
+            select("select * where extract(dow from title) = 5"),
+            Err(Sql(SqlError::format(
+                "[DATE | EXTRACT](date)",
+                "EXTRACT(DOW FROM title)"
+            )))
+        );
+        assert_eq!(
+            select("select * where extract(epoch from date) = 5"),
+            Err(Sql(UnhandledFunction("EXTRACT(EPOCH FROM ..)".into())))
+        );
+    }
+}
+
+#[cfg(test)]
+mod execute {
+    use super::*;
+    use command::Output::*;
+
+    #[test]
+    fn update() {
+        let mut list = fixture();
+
+        assert_eq!(
+            list.execute(
+                "update tasks set is_done = true, descr = 'готово' where category = 'Работа'"
+            ),
+            Ok(Updated(2))
+        );
+        assert_eq!(
+            list.iter()
+                .map(|task| (
+                    task.status == Status::Done,
+                    task.descr.as_deref().unwrap_or("")
+                ))
+                .collect::<Vec<_>>(),
+            vec![(true, "готово"), (false, ""), (true, "готово")]
+        );
+
+        // Без WHERE обновляются все задачи
+        assert_eq!(
+            list.execute("update tasks set date = \"2024-09-01 00:00\""),
+            Ok(Updated(3))
+        );
+        assert!(list
+            .iter()
+            .all(|task| task.date == Some("2024-09-01 00:00".parse().unwrap())));
+
+        // Проверка типов как в WHERE
+        assert_eq!(
+            list.execute("update tasks set is_done = 'true'"),
+            Err(Sql(SqlError::format("[.. true | false]", "'true'")))
+        );
+        assert_eq!(
+            list.execute("update tasks set name = ''"),
+            Err(Sql(NonExistentField("name".into())))
+        );
+        assert_eq!(
+            list.execute("update todo set title = ''"),
+            Err(Sql(NonExistentTable("todo".into())))
+        );
+    }
+
+    #[test]
+    fn delete() {
+        let mut list = fixture();
+
+        assert_eq!(
+            list.execute("delete from tasks where date < \"2024-01-01 00:00\""),
+            Ok(Deleted(1))
+        );
+        assert_eq!(list.len(), 2);
+        assert_eq!(
+            list.execute("delete from tasks where title = 'нет такой'"),
+            Ok(Deleted(0))
+        );
+        assert_eq!(list.execute("delete from tasks"), Ok(Deleted(2)));
+        assert!(list.is_empty());
+
+        assert_eq!(
+            list.execute("delete from todo"),
+            Err(Sql(NonExistentTable("todo".into())))
+        );
+    }
+
+    #[test]
+    fn insert() {
+        let mut list = List::from(vec![]);
+
+        assert_eq!(
+            list.execute(
+                "insert into tasks (title, date, category) values ('Отчет', \"2024-08-20 12:00\", 'Работа'), ('Уборка', \"2024-08-21 12:00\", 'Дом')"
+            ),
+            Ok(Inserted(2))
+        );
+        assert_eq!(
+            list[0],
+            Task {
+                id: 1,
+                title: "Отчет".into(),
+                date: Some("2024-08-20 12:00".parse().unwrap()),
+                category: Some("Работа".into()),
+                ..Task::default()
+            }
+        );
+        assert_eq!(list[1].id, 2);
+
+        // Без списка столбцов - все поля по порядку, кроме id
+        assert_eq!(
+            list.execute(
+                "insert into tasks values ('Созвон', 'С клиентом', \"2024-08-22 12:00\", 'Работа', ['клиент', 'звонок'], 'done', 'urgent', 1, [1, 2], 'rrule:freq=daily')"
+            ),
+            Ok(Inserted(1))
+        );
+        assert_eq!(list[2].status, Status::Done);
+        assert_eq!(list[2].priority, Priority::Urgent);
+        assert_eq!(list[2].tags, vec!["клиент", "звонок"]);
+        assert_eq!(list[2].parent, 1);
+        assert_eq!(list[2].depends_on, vec![1, 2]);
+        assert_eq!(list[2].repeat.to_string(), "FREQ=DAILY");
+
+        // Дубликат откатывает всю вставку
+        assert_eq!(
+            list.execute(
+                "insert into tasks (title, date, category) values ('Новая', \"2024-08-20 12:00\", ''), ('Отчет', \"2024-08-20 12:00\", 'Работа')"
+            ),
+            Err(TaskAlreadyExists)
+        );
+        assert_eq!(list.len(), 3);
+
+        assert_eq!(
+            list.execute("insert into tasks (descr) values ('Без названия')"),
+            Err(Sql(MissingField("title".into())))
+        );
+        assert_eq!(
+            list.execute("insert into tasks (title, date) values ('Без даты')"),
+            Err(Sql(SqlError::format(
+                "VALUES (title, date)",
+                "('Без даты')"
+            )))
+        );
+        assert_eq!(
+            list.execute("insert into tasks (title, date) values ('a', false)"),
+            Err(Sql(SqlError::format("[.. \"Date\"]", "false")))
+        );
+        assert_eq!(
+            list.execute("insert into tasks (id, title, date) values (7, 'a', '2024-08-20 12:00')"),
+            Err(Sql(ReadOnlyField("id".into())))
+        );
+        assert_eq!(
+            list.execute("update tasks set id = 7"),
+            Err(Sql(ReadOnlyField("id".into())))
+        );
+    }
+
+    #[test]
+    fn select() {
+        let mut list = fixture();
+
+        assert_eq!(
+            list.execute("select title from tasks where category = 'Дом'"),
+            Ok(Rows(rows::Rows {
+                columns: vec![rows::Column::new("title")],
+                rows: vec![vec!["Уборка".into()]],
+            }))
+        );
+        assert_eq!(
+            list.execute("select * from todo"),
+            Err(Sql(NonExistentTable("todo".into())))
+        );
+        assert_eq!(
+            list.execute("select * from tasks; delete from tasks"),
+            Err(Sql(NotValidQuery))
+        );
+    }
+}
+
+#[cfg(test)]
+mod diagnostic {
+    use super::*;
+    use crate::list::diagnostic::{render, Span};
+
+    fn error(sql: &str) -> SqlError {
+        let Err(Sql(error)) = List::from(vec![]).select(sql) else {
+            unreachable!()
+        };
+        error
+    }
+
+    #[test]
+    fn spans() {
+        // Позиция из sqlparser
+        let sql = "select * where\n  title = = 2";
+        assert_eq!(
+            error(sql).span(),
+            Some(Span {
+                line: 2,
+                column: 11,
+                len: 1
+            })
+        );
+
+        // Позиция по найденному фрагменту
+        let sql = "select * where title = 'a' and is_done = 'да'";
+        let err = error(sql);
+        assert_eq!(err, SqlError::format("[.. true | false]", "'да'"));
+        assert_eq!(
+            err.span(),
+            Some(Span {
+                line: 1,
+                column: 42,
+                len: 4
+            })
+        );
+
+        let sql = "SELECT * WHERE date < NOW() + INTERVAL '3 fortnights'";
+        assert_eq!(
+            error(sql).span(),
+            Some(Span {
+                line: 1,
+                column: 31,
+                len: 23
+            })
+        );
+
+        let sql = "select * where name = ''";
+        assert_eq!(
+            error(sql).span(),
+            Some(Span {
+                line: 1,
+                column: 16,
+                len: 4
+            })
+        );
+
+        // Повторяющийся фрагмент: место берется из неверного элемента, а не первое вхождение
+        let span = |column, len| {
+            Some(Span {
+                line: 1,
+                column,
+                len,
+            })
+        };
+        let sql = "select * where title = 'a' and date = 'a'";
+        assert_eq!(error(sql).span(), span(39, 3));
+        let sql = "select * where id = 5 or title = 5";
+        assert_eq!(error(sql).span(), span(34, 1));
+        let sql = "update tasks set descr = 'a', date = 'a' where title = 'a'";
+        let Err(Sql(err)) = List::from(vec![]).execute(sql) else {
+            unreachable!()
+        };
+        assert_eq!(err.span(), span(38, 3));
+
+        // Ошибки без места в запросе
+        let sql = "select * from tasks; select *";
+        assert_eq!(error(sql).span(), None);
+    }
+
+    #[test]
+    fn render_caret() {
+        let sql = "select title\nwhere title || 'a'";
+        assert_eq!(
+            render(sql, &error(sql)),
+            Some("where title || 'a'\n            ^^".into())
+        );
+
+        let sql = "select * where";
+        assert_eq!(
+            render(sql, &error(sql)),
+            Some("select * where\n              ^".into())
+        );
+    }
+}
+
+#[cfg(test)]
+mod planner {
+    use super::*;
+    use crate::list::planner::{Lookup, Plan};
+    use std::ops::Bound;
+
+    fn plan(list: &List, sql: &str) -> Plan {
+        let query = sql.parse::<SelectQuery>().unwrap();
+        Plan::new(list, query.condition.as_ref())
+    }
+
+    #[test]
+    fn index_maintenance() {
+        let mut list = fixture();
+        let check = |list: &List| assert_eq!(*list.index(), Index::new(list));
+
+        list.done("Уборка".into()).unwrap();
+        check(&list);
+        list.update(
+            "Отчет".into(),
+            &Task {
+                title: "Отчет за год".into(),
+                category: Some("Дом".into()),
+                ..list.get_task("Отчет").unwrap().clone()
+            },
+        )
+        .unwrap();
+        check(&list);
+        assert_eq!(list.get_task("Отчет"), Err(TaskNotExist("Отчет".into())));
+        assert_eq!(
+            list.get_task("Отчет за год").unwrap().category.as_deref(),
+            Some("Дом")
+        );
+
+        list.delete("Уборка".into()).unwrap();
+        check(&list);
+        assert_eq!(list.get_task("Созвон").unwrap().title, "Созвон");
+
+        list.execute("update tasks set category = 'Личное' where is_done = false")
+            .unwrap();
+        check(&list);
+        list.execute("insert into tasks (title, date) values ('Новая', '2024-09-01 10:00')")
+            .unwrap();
+        check(&list);
+        list.execute("delete from tasks where category = 'Личное'")
+            .unwrap();
+        check(&list);
+
+        let list = serde_json::from_str::<List>(&serde_json::to_string(&list).unwrap()).unwrap();
+        check(&list);
+    }
+
+    #[test]
+    fn plan_choice() {
+        let done = |task| Task {
+            status: Status::Done,
+            ..task
+        };
+        let list = List::from(vec![
+            done(task("Отчет", "Работа", "2023-12-20 12:00")),
+            task("Уборка", "Дом", "2024-08-01 12:00"),
+            task("Созвон", "Работа", "2024-08-02 12:00"),
+            done(task("Покупки", "Дом", "2024-08-03 12:00")),
+        ]);
+
+        assert_eq!(plan(&list, "select *"), Plan::FullScan);
+        // OR и шаблоны по индексу не отвечаются
+        assert_eq!(
+            plan(&list, "select * where title = 'Отчет' or is_done = true"),
+            Plan::FullScan
+        );
+        assert_eq!(
+            plan(&list, "select * where title like 'О%'"),
+            Plan::FullScan
+        );
+        // Условие под все задачи не лучше полного перебора
+        assert_eq!(
+            plan(&list, "select * where date > '2020-01-01 00:00'"),
+            Plan::FullScan
+        );
+
+        assert_eq!(
+            plan(
+                &list,
+                "select * where category = 'Работа' and title = 'Созвон'"
+            ),
+            Plan::Index {
+                lookup: Lookup::Title(vec!["Созвон".into()]),
+                estimate: 1,
+            }
+        );
+        assert_eq!(
+            plan(
+                &list,
+                "select * where is_done = false and date >= '2024-08-02 00:00' and descr = ''"
+            ),
+            Plan::Index {
+                lookup: Lookup::Status(vec![
+                    Status::Todo,
+                    Status::InProgress,
+                    Status::Waiting,
+                    Status::Deferred,
+                    Status::Cancelled
+                ]),
+                estimate: 2,
+            }
+        );
+        // Диапазоны дат из разных слагаемых пересекаются
+        assert_eq!(
+            plan(
+                &list,
+                "select * where date > '2024-08-01 13:00' and is_done = false \
+                 and date <= '2024-08-02 12:00'"
+            ),
+            Plan::Index {
+                lookup: Lookup::Date(
+                    Bound::Excluded("2024-08-01 13:00".parse().unwrap()),
+                    Bound::Included("2024-08-02 12:00".parse().unwrap())
+                ),
+                estimate: 1,
+            }
+        );
+        assert_eq!(
+            plan(
+                &list,
+                "select * where date > '2024-08-03 00:00' and date < '2024-08-01 00:00'"
+            ),
+            Plan::Index {
+                lookup: Lookup::Date(
+                    Bound::Excluded("2024-08-03 00:00".parse().unwrap()),
+                    Bound::Excluded("2024-08-01 00:00".parse().unwrap())
+                ),
+                estimate: 0,
+            }
+        );
+
+        // Результат совпадает с полным перебором
+        for sql in [
+            "select * where category in ('Дом', 'Отпуск') and is_done = true",
+            "select * where date between '2024-08-01 12:00' and '2024-08-02 12:00'",
+            "select * where title = 'Нет такой'",
+            "select * where date >= '2024-08-03 12:00' and date <= '2024-08-03 12:00'",
+        ] {
+            let query = sql.parse::<SelectQuery>().unwrap();
+            assert_eq!(
+                list.select(sql).unwrap(),
+                list.iter()
+                    .filter(|task| query.condition.as_ref().unwrap().is_match(task))
+                    .collect()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod prepared {
+    use super::*;
+    use command::Output::*;
+
+    #[test]
+    fn bind() {
+        let mut list = List::from(vec![
+            task("Отчет 'итоговый'", "Работа", "2023-12-20 12:00"),
+            task("Уборка", "Дом", "2024-08-01 12:00"),
+            task("Созвон", "Работа", "2024-08-02 12:00"),
+        ]);
+        let date = |date: &str| date.parse::<Date>().unwrap().into();
+
+        // Кавычки в значении не ломают запрос
+        let select = List::prepare("select * where title = ?").unwrap();
+        assert_eq!(select.params(), 1);
+        assert_eq!(
+            select.select(&list, &["Отчет 'итоговый'".into()]),
+            Ok(List::from(vec![list[0].clone()]))
+        );
+        assert_eq!(
+            select.select(&list, &["Созвон".into()]),
+            Ok(List::from(vec![list[2].clone()]))
+        );
+
+        let select = List::prepare(
+            "select title where category in ($1, 'Учеба') and date between $2 and $3 \
+             and extract(year from date) = $4",
+        )
+        .unwrap();
+        assert_eq!(
+            select
+                .query(
+                    &list,
+                    &[
+                        "Работа".into(),
+                        date("2024-01-01 00:00"),
+                        date("2024-12-31 00:00"),
+                        2024.into(),
+                    ]
+                )
+                .unwrap()
+                .rows,
+            vec![vec![serde_json::json!("Созвон")]]
+        );
+
+        let update =
+            List::prepare("update tasks set is_done = ?, descr = ? where category = ?").unwrap();
+        assert_eq!(
+            update.execute(&mut list, &[true.into(), "Готово".into(), "Дом".into()]),
+            Ok(Updated(1))
+        );
+        assert_eq!(list.get_task("Уборка").unwrap().status, Status::Done);
+
+        let insert = List::prepare("insert into tasks (title, date) values ($1, $2)").unwrap();
+        assert_eq!(
+            insert.execute(&mut list, &["Новая".into(), date("2024-09-01 10:00")]),
+            Ok(Inserted(1))
+        );
+        assert_eq!(
+            List::prepare("delete from tasks where title = $1 or descr = $1")
+                .unwrap()
+                .execute(&mut list, &["Готово".into()]),
+            Ok(Deleted(1))
+        );
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn errors() {
+        let list = fixture();
+
+        let select = List::prepare("select * where title = ? and date > ?").unwrap();
+        assert_eq!(
+            select.select(&list, &["Созвон".into()]),
+            Err(Sql(ParamCount {
+                expected: 2,
+                found: 1
+            }))
+        );
+        assert_eq!(
+            select.select(&list, &["Созвон".into(), "2024-01-01".into()]),
+            Err(Sql(ParamType {
+                number: 2,
+                expected: "Date".into(),
+                found: "String".into()
+            }))
+        );
+        // Параметры без значений в обычном запросе
+        assert_eq!(
+            list.select("select * where is_done = $1"),
+            Err(Sql(ParamCount {
+                expected: 1,
+                found: 0
+            }))
+        );
+
+        assert_eq!(
+            List::prepare("select * where title = ? and descr = $2"),
+            Err(Sql(SqlError::format("?", "$2")))
+        );
+        assert_eq!(
+            List::prepare("select * where title = :title"),
+            Err(Sql(SqlError::format("? | $Number", ":title")))
+        );
+        assert_eq!(
+            List::prepare("select * where name = ?"),
+            Err(Sql(NonExistentField("name".into())))
+        );
+    }
+}
+
+#[cfg(test)]
+mod explain {
+    use super::*;
+    use crate::list::explain::Step;
+    use crate::list::planner::{Lookup, Plan};
+
+    fn step(depth: usize, label: &str, input: usize, output: usize) -> Step {
+        Step {
+            depth,
+            label: label.into(),
+            input,
+            output,
+        }
+    }
+
+    #[test]
+    fn steps() {
+        let task = |title, category, status| Task {
+            status,
+            ..task(title, category, "2024-08-01 12:00")
+        };
+        let list = List::from(vec![
+            task("Отчет", "Работа", Status::Done),
+            task("Уборка", "Дом", Status::Todo),
+            task("Созвон", "Работа", Status::Todo),
+            task("Покупки", "Дом", Status::Todo),
+        ]);
+        let explain = list
+            .explain(
+                "explain select title where is_done = false \
+                 and (title like 'У%' or category = 'Работа') and not date > '2024-09-01 00:00' \
+                 order by date",
+            )
+            .unwrap();
+
+        assert_eq!(
+            explain.plan,
+            Plan::Index {
+                lookup: Lookup::Status(vec![
+                    Status::Todo,
+                    Status::InProgress,
+                    Status::Waiting,
+                    Status::Deferred,
+                    Status::Cancelled
+                ]),
+                estimate: 3
+            }
+        );
+        assert_eq!(
+            explain.fields,
+            vec![
+                ("title".into(), "String"),
+                ("date".into(), "Option<Date>"),
+                ("category".into(), "Option<String>"),
+                ("is_done".into(), "bool"),
+            ]
+        );
+        assert_eq!((explain.total, explain.candidates, explain.rows), (4, 3, 2));
+        assert_eq!(
+            explain.steps,
+            vec![
+                step(0, "AND", 3, 2),
+                step(1, "AND", 3, 2),
+                step(2, "is_done = false", 3, 3),
+                step(2, "OR", 3, 2),
+                step(3, "title ~ '^У.*$'", 3, 1),
+                step(3, "category = 'Работа'", 2, 1),
+                step(1, "NOT", 2, 2),
+                step(2, "date > '2024-09-01 00:00'", 2, 0),
+            ]
+        );
+
+        // Условие, отсеявшее все задачи
+        let explain = list
+            .explain("select * where category = 'Дом' and is_done = true")
+            .unwrap();
+        assert_eq!(explain.rows, 0);
+        assert_eq!(
+            explain.steps,
+            vec![
+                step(0, "AND", 1, 0),
+                step(1, "category = 'Дом'", 1, 0),
+                step(1, "is_done = true", 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn execute() {
+        let mut list = fixture();
+        let output = list
+            .execute("EXPLAIN SELECT count(*) WHERE title IN ('Отчет', 'Созвон')")
+            .unwrap();
+        let Output::Explain(explain) = &output else {
+            panic!("{:?}", output);
+        };
+        assert_eq!(
+            explain.steps,
+            vec![step(0, "title IN ('Отчет', 'Созвон')", 2, 2)]
+        );
+        assert_eq!(explain.rows, 1);
+
+        assert_eq!(list.explain("delete from tasks"), Err(Sql(NotValidQuery)));
+        assert_eq!(list.select("explain select *"), Err(Sql(NotValidQuery)));
+    }
+}
+
+#[cfg(test)]
+mod search {
+    use super::*;
+    use crate::list::search::Tokenizer;
+
+    fn task(title: &str, descr: &str, category: &str) -> Task {
+        Task {
+            title: title.into(),
+            descr: Some(descr.into()),
+            category: Some(category.into()),
+            ..Task::default()
+        }
+    }
+
+    #[test]
+    fn tokens() {
+        let tokenizer = Tokenizer::default();
+        let terms = |text: &str| {
+            tokenizer
+                .tokens(text)
+                .into_iter()
+                .map(|token| token.term)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(terms("Отчёты, ОТЧЕТ; отчета"), vec!["отчет"; 3]);
+        assert_eq!(terms("Reports reporting REPORT"), vec!["report"; 3]);
+        assert_eq!(terms("  -- "), Vec::<String>::new());
+        assert_eq!(tokenizer.tokens("о, Q3")[1].range, 4..6);
+
+        assert_eq!(
+            tokenizer.highlight(
+                "Отчеты за квартал, отчет.",
+                &["отчет".into()],
+                |word| { format!("[{word}]") }
+            ),
+            "[Отчеты] за квартал, [отчет]."
+        );
+    }
+
+    #[test]
+    fn ranking() {
+        let list = List::from(vec![
+            task("Купить продукты", "и отчет не забыть", "Дом"),
+            task(
+                "Отчет за квартал",
+                "Квартальный отчет для бухгалтерии",
+                "Работа",
+            ),
+            task("Позвонить", "", "Отчеты"),
+            task("Write report", "quarterly", "Work"),
+        ]);
+        let titles = |query: &str| {
+            list.search(query)
+                .into_iter()
+                .map(|hit| hit.task.title.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        // Совпадение в названии весит больше, чем в категории и описании
+        assert_eq!(
+            titles("отчеты"),
+            vec!["Отчет за квартал", "Позвонить", "Купить продукты"]
+        );
+        // Больше совпавших слов - выше в выдаче
+        assert_eq!(
+            titles("продукты отчет"),
+            vec!["Купить продукты", "Отчет за квартал", "Позвонить"]
+        );
+        assert_eq!(titles("Reporting QUARTERLY"), vec!["Write report"]);
+        assert_eq!(titles("несуществующее"), Vec::<&str>::new());
+
+        let hits = list.search("квартал отчет");
+        assert_eq!(hits[0].terms, vec!["кварта", "отчет"]);
+    }
+}
+
+#[cfg(test)]
+mod tree {
+    use super::*;
+    use command::Output::*;
+
+    #[test]
+    fn hierarchy() {
+        // Переезд(1): Упаковать(2): Книги(3), Посуда(4); Перевезти(5). Отпуск(6)
+        let task = |id, title: &str, parent| Task {
+            id,
+            title: title.into(),
+            parent,
+            ..Task::default()
+        };
+        let mut list = List::from(vec![
+            task(1, "Переезд", 0),
+            task(2, "Упаковать", 1),
+            task(3, "Книги", 2),
+            task(4, "Посуда", 2),
+            task(5, "Перевезти", 1),
+            task(6, "Отпуск", 0),
+        ]);
+
+        assert_eq!(list.subtree(1), vec![1, 2, 3, 4, 5]);
+        assert_eq!(list.subtree(4), vec![4]);
+        assert_eq!(list.subtree(42), Vec::<u64>::new());
+
+        // Подзадача, добавленная позже, выводится под родителем
+        list.add(Task {
+            title: "Документы".into(),
+            parent: 1,
+            ..Task::default()
+        })
+        .unwrap();
+        assert_eq!(
+            list.tree(),
+            vec![(0, 0), (1, 1), (2, 2), (3, 2), (4, 1), (6, 1), (5, 0)]
+        );
+        assert_eq!(
+            list.children(1).map(|task| task.id).collect::<Vec<_>>(),
+            vec![2, 5, 7]
+        );
+
+        assert_eq!(
+            list.add(Task {
+                title: "Сирота".into(),
+                parent: 42,
+                ..Task::default()
+            }),
+            Err(ParentNotExist(42))
+        );
+        let packing = list[1].clone();
+        let task = |parent| Task {
+            parent,
+            ..packing.clone()
+        };
+        assert_eq!(list.update("2".into(), &task(3)), Err(CyclicParent(2)));
+        assert_eq!(list.update("2".into(), &task(2)), Err(CyclicParent(2)));
+        assert_eq!(list.update("2".into(), &task(6)).map(|t| t.parent), Ok(6));
+
+        // Удаление забирает все поддерево
+        assert_eq!(list.delete("Отпуск".into()).map(|t| t.id), Ok(6));
+        assert_eq!(titles(&list), vec!["Переезд", "Перевезти", "Документы"]);
+    }
+
+    #[test]
+    fn roll_up() {
+        // Переезд(1): Упаковать(2): Книги(3), Посуда(4); Перевезти(5). Отпуск(6)
+        let task = |id, title: &str, parent| Task {
+            id,
+            title: title.into(),
+            parent,
+            ..Task::default()
+        };
+        let mut list = List::from(vec![
+            task(1, "Переезд", 0),
+            task(2, "Упаковать", 1),
+            task(3, "Книги", 2),
+            task(4, "Посуда", 2),
+            task(5, "Перевезти", 1),
+            task(6, "Отпуск", 0),
+        ]);
+
+        list.done("Книги".into()).unwrap();
+        assert_eq!(list.complete_parents(3), Vec::<u64>::new());
+        assert_eq!(list.progress(2), (1, 2));
+
+        list.done("Посуда".into()).unwrap();
+        assert_eq!(list.complete_parents(4), vec![2]);
+        assert_eq!(list[1].status, Status::Done);
+        assert_eq!(list.progress(1), (1, 2));
+
+        let table = list.to_string();
+        assert!(table.contains("\"Переезд\" [1/2]"));
+        assert!(table.contains("└ \"Упаковать\" [2/2]"));
+        assert!(table.contains("  └ \"Книги\""));
+    }
+
+    #[test]
+    fn sql() {
+        // Переезд(1): Упаковать(2): Книги(3), Посуда(4); Перевезти(5). Отпуск(6)
+        let task = |id, title: &str, parent| Task {
+            id,
+            title: title.into(),
+            parent,
+            ..Task::default()
+        };
+        let mut list = List::from(vec![
+            task(1, "Переезд", 0),
+            task(2, "Упаковать", 1),
+            task(3, "Книги", 2),
+            task(4, "Посуда", 2),
+            task(5, "Перевезти", 1),
+            task(6, "Отпуск", 0),
+        ]);
+
+        assert_eq!(
+            list.select("select * where subtree(2)")
+                .map(|l| titles(&l).join(", ")),
+            Ok("Упаковать, Книги, Посуда".into())
+        );
+        assert_eq!(
+            list.select("select * where parent = 1 or subtree(6)")
+                .map(|l| titles(&l).join(", ")),
+            Ok("Упаковать, Перевезти, Отпуск".into())
+        );
+        assert_eq!(
+            List::prepare("select * where not subtree(?)")
+                .and_then(|query| query.select(&list, &[1.into()]))
+                .map(|l| titles(&l).join(", ")),
+            Ok("Отпуск".into())
+        );
+        assert_eq!(
+            list.select("select * where subtree('Упаковать')"),
+            Err(Sql(SqlError::format("SUBTREE(Number)", "'Упаковать'")))
+        );
+
+        assert_eq!(
+            list.execute("update tasks set parent = 3 where id = 1"),
+            Err(CyclicParent(1))
+        );
+        assert_eq!(
+            list.execute("update tasks set parent = 9 where title = 'Отпуск'"),
+            Err(ParentNotExist(9))
+        );
+        assert_eq!(list.subtree(1), vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            list.execute("update tasks set parent = 5 where subtree(2) and parent = 1"),
+            Ok(Updated(1))
+        );
+        assert_eq!(list.subtree(5), vec![5, 2, 3, 4]);
+
+        assert_eq!(
+            list.execute("delete from tasks where id = 5"),
+            Ok(Deleted(4))
+        );
+        assert_eq!(titles(&list), vec!["Переезд", "Отпуск"]);
+    }
+}
+
+#[cfg(test)]
+mod depends {
+    use super::*;
+    use command::Output::*;
+
+    fn next(list: &List) -> Vec<u64> {
+        list.next().iter().map(|task| task.id).collect()
+    }
+
+    #[test]
+    fn blocked() {
+        // Купить краску(1), Покрасить стены(2) после 1, Повесить полки(3) после 2, Отдых(4)
+        let task = |id, title: &str, depends_on| Task {
+            id,
+            title: title.into(),
+            depends_on,
+            ..Task::default()
+        };
+        let mut list = List::from(vec![
+            task(1, "Купить краску", vec![]),
+            task(2, "Покрасить стены", vec![1]),
+            task(3, "Повесить полки", vec![2]),
+            task(4, "Отдых", vec![]),
+        ]);
+
+        assert_eq!(next(&list), vec![1, 4, 2, 3]);
+        assert_eq!(list.blockers(&list[2]), vec![2]);
+        assert_eq!(list.done("3".into()), Err(TaskBlocked(vec![2])));
+
+        list.done("Купить краску".into()).unwrap();
+        assert!(!list.is_blocked(&list[1]));
+        assert_eq!(next(&list), vec![2, 4, 3]);
+
+        // Циклы и несуществующие задачи
+        assert_eq!(
+            list.depend("1".into(), &["3".into()]),
+            Err(CyclicDependency(1))
+        );
+        assert_eq!(
+            list.depend("1".into(), &["1".into()]),
+            Err(CyclicDependency(1))
+        );
+        assert_eq!(
+            list.depend("1".into(), &["Ремонт".into()]),
+            Err(TaskNotExist("Ремонт".into()))
+        );
+        assert_eq!(
+            list.depend("Отдых".into(), &["3".into(), "2".into(), "3".into()])
+                .map(|task| task.depends_on.clone()),
+            Ok(vec![3, 2])
+        );
+        assert_eq!(next(&list), vec![2, 3, 4]);
+        assert_eq!(
+            list.undepend("Отдых".into(), &["3".into()])
+                .map(|task| task.depends_on.clone()),
+            Ok(vec![2])
+        );
+
+        // Удаленная задача больше не блокирует
+        list.delete("Покрасить стены".into()).unwrap();
+        assert_eq!(list[1].depends_on, Vec::<u64>::new());
+        assert_eq!(
+            list.done("3".into()).map(|task| task.status),
+            Ok(Status::Done)
+        );
+    }
+
+    #[test]
+    fn sql() {
+        // Купить краску(1), Покрасить стены(2) после 1, Повесить полки(3) после 2, Отдых(4)
+        let task = |id, title: &str, depends_on| Task {
+            id,
+            title: title.into(),
+            depends_on,
+            ..Task::default()
+        };
+        let mut list = List::from(vec![
+            task(1, "Купить краску", vec![]),
+            task(2, "Покрасить стены", vec![1]),
+            task(3, "Повесить полки", vec![2]),
+            task(4, "Отдых", vec![]),
+        ]);
+
+        assert_eq!(
+            list.select("select * where is_blocked = false")
+                .map(|l| l.iter().map(|task| task.id).collect::<Vec<_>>()),
+            Ok(vec![1, 4])
+        );
+        assert_eq!(
+            list.query("select title, is_blocked where 1 in depends_on or depends_on contains 2")
+                .map(|rows| rows.rows),
+            Ok(vec![
+                vec!["Покрасить стены".into(), true.into()],
+                vec!["Повесить полки".into(), true.into()],
+            ])
+        );
+        assert_eq!(
+            list.query("select is_blocked, count(*) group by is_blocked"),
+            Err(Sql(NonExistentField("is_blocked".into())))
+        );
+        assert_eq!(
+            list.execute("update tasks set is_blocked = false"),
+            Err(Sql(ReadOnlyField("is_blocked".into())))
+        );
+
+        assert_eq!(
+            list.execute("update tasks set depends_on = [3] where id = 1"),
+            Err(CyclicDependency(1))
+        );
+        assert_eq!(
+            list.execute("update tasks set depends_on = [9] where id = 4"),
+            Err(DependencyNotExist(9))
+        );
+        assert_eq!(
+            list.execute("update tasks set depends_on = [1, 2, 1] where id = 4"),
+            Ok(Updated(1))
+        );
+        assert_eq!(list[3].depends_on, vec![1, 2]);
+        assert_eq!(
+            list.execute("update tasks set is_done = true where is_blocked = false"),
+            Ok(Updated(1))
+        );
+        assert_eq!(list[0].status, Status::Done);
+
+        // Зависимости проверяются после всех изменений запроса
+        assert_eq!(
+            list.execute("update tasks set is_done = true where id = 3"),
+            Err(TaskBlocked(vec![2]))
+        );
+        assert_eq!(list[2].status, Status::Todo);
+        assert_eq!(
+            list.execute("update tasks set is_done = true where id in (2, 3)"),
+            Ok(Updated(2))
+        );
+    }
+
+    #[test]
+    fn parents() {
+        // Купить краску(1), Покрасить стены(2) после 1, Повесить полки(3) после 2, Отдых(4)
+        let task = |id, title: &str, depends_on| Task {
+            id,
+            title: title.into(),
+            depends_on,
+            ..Task::default()
+        };
+        let mut list = List::from(vec![
+            task(1, "Купить краску", vec![]),
+            task(2, "Покрасить стены", vec![1]),
+            task(3, "Повесить полки", vec![2]),
+            task(4, "Отдых", vec![]),
+        ]);
+        let parent = list
+            .add(Task {
+                title: "Ремонт".into(),
+                depends_on: vec![1],
+                ..Task::default()
+            })
+            .unwrap()
+            .id;
+        list.execute(&format!("update tasks set parent = {parent} where id = 4"))
+            .unwrap();
+
+        // Родитель с невыполненной зависимостью не выполняется вместе с подзадачами
+        list.done("Отдых".into()).unwrap();
+        assert_eq!(list.complete_parents(4), Vec::<u64>::new());
+        assert_eq!(list[4].status, Status::Todo);
+
+        list.done("Купить краску".into()).unwrap();
+        assert_eq!(list.complete_parents(4), vec![parent]);
+    }
+}
+
+#[cfg(test)]
+mod recurrence {
+    use super::*;
+    use command::Output::*;
+
+    fn next(rule: &str, date: &str) -> Option<(String, String)> {
+        let repeat = rule.parse::<Recurrence>().unwrap();
+        repeat
+            .next(&date.parse().unwrap())
+            .map(|(date, repeat)| (date.to_string(), repeat.to_string()))
+    }
+
+    #[test]
+    fn parse() {
+        let repeat = "rrule:freq=weekly; byday=fr,mo ;interval=2".parse::<Recurrence>();
+        assert_eq!(
+            repeat.map(|repeat| repeat.to_string()),
+            Ok("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR".into())
+        );
+        assert_eq!(
+            "FREQ=DAILY;UNTIL=20241231"
+                .parse::<Recurrence>()
+                .map(|repeat| repeat.to_string()),
+            Ok("FREQ=DAILY;UNTIL=20241231".into())
+        );
+        assert!("".parse::<Recurrence>().unwrap().is_none());
+
+        for rule in [
+            "DAILY",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;BYDAY=MO",
+            "FREQ=WEEKLY;BYDAY=XX",
+            "FREQ=DAILY;COUNT=2;UNTIL=20241231",
+            "INTERVAL=2",
+        ] {
+            assert!(rule.parse::<Recurrence>().is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn next_date() {
+        // 2024-08-20 - вторник
+        assert_eq!(
+            next("FREQ=DAILY;INTERVAL=3", "2024-08-20 12:00").map(|n| n.0),
+            Some("2024-08-23 12:00".into())
+        );
+        assert_eq!(
+            next("FREQ=WEEKLY;BYDAY=MO,FR", "2024-08-20 12:00").map(|n| n.0),
+            Some("2024-08-23 12:00".into())
+        );
+        assert_eq!(
+            next("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR", "2024-08-23 12:00").map(|n| n.0),
+            Some("2024-09-02 12:00".into())
+        );
+        // Месяцы без такого дня пропускаются: 31 января -> 31 марта -> 31 мая
+        assert_eq!(
+            next("FREQ=MONTHLY", "2024-01-31 09:00").map(|n| n.0),
+            Some("2024-03-31 09:00".into())
+        );
+        assert_eq!(
+            next("FREQ=MONTHLY", "2024-03-31 09:00").map(|n| n.0),
+            Some("2024-05-31 09:00".into())
+        );
+        assert_eq!(
+            next("FREQ=MONTHLY;INTERVAL=2", "2024-08-31 09:00").map(|n| n.0),
+            Some("2024-10-31 09:00".into())
+        );
+        assert_eq!(
+            next("FREQ=MONTHLY", "2024-01-30 09:00").map(|n| n.0),
+            Some("2024-03-30 09:00".into())
+        );
+        assert_eq!(
+            next("FREQ=YEARLY", "2024-02-29 09:00").map(|n| n.0),
+            Some("2028-02-29 09:00".into())
+        );
+
+        // Повторения заканчиваются
+        assert_eq!(
+            next("FREQ=DAILY;COUNT=2", "2024-08-20 12:00"),
+            Some(("2024-08-21 12:00".into(), "FREQ=DAILY;COUNT=1".into()))
+        );
+        assert_eq!(next("FREQ=DAILY;COUNT=1", "2024-08-20 12:00"), None);
+        assert_eq!(next("FREQ=WEEKLY;UNTIL=20240826", "2024-08-20 12:00"), None);
+
+        // Следующая дата за пределами chrono - повторений тоже нет
+        for rule in [
+            "FREQ=DAILY;INTERVAL=200000000",
+            "FREQ=WEEKLY;INTERVAL=4000000000",
+            "FREQ=WEEKLY;INTERVAL=4000000000;BYDAY=MO",
+            "FREQ=MONTHLY;INTERVAL=4000000000",
+            "FREQ=YEARLY;INTERVAL=400000000",
+        ] {
+            assert_eq!(next(rule, "2024-08-20 12:00"), None, "{}", rule);
+            let mut list = List::from(vec![Task {
+                title: "Далеко".into(),
+                date: Some("2024-08-20 12:00".parse().unwrap()),
+                repeat: rule.parse().unwrap(),
+                ..Task::default()
+            }]);
+            assert_eq!(
+                list.done("Далеко".into()).map(|task| task.status),
+                Ok(Status::Done)
+            );
+            assert_eq!(list.len(), 1);
+        }
+    }
+
+    #[test]
+    fn done() {
+        let mut list = List::from(vec![]);
+        list.add(Task {
+            title: "Полить цветы".into(),
+            date: Some("2024-08-20 12:00".parse().unwrap()),
+            repeat: "FREQ=WEEKLY".parse().unwrap(),
+            ..Task::default()
+        })
+        .unwrap();
+
+        assert!(list.done("Полить цветы".into()).unwrap().repeat.is_none());
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[1].id, 2);
+        assert_eq!(list[1].status, Status::Todo);
+        assert_eq!(list[1].date, Some("2024-08-27 12:00".parse().unwrap()));
+        assert_eq!(list[1].repeat.to_string(), "FREQ=WEEKLY");
+
+        assert_eq!(
+            list.select("select * where repeat = 'rrule:freq=weekly'")
+                .map(|l| l.iter().map(|task| task.id).collect::<Vec<_>>()),
+            Ok(vec![2])
+        );
+        assert_eq!(
+            list.execute("update tasks set repeat = 'FREQ=DAILY' where id = 2"),
+            Ok(Updated(1))
+        );
+        assert_eq!(list[1].repeat.to_string(), "FREQ=DAILY");
+
+        // UPDATE тоже порождает следующее повторение
+        assert_eq!(
+            list.execute("update tasks set is_done = true where id = 2"),
+            Ok(Updated(1))
+        );
+        assert!(list[1].repeat.is_none());
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[2].status, Status::Todo);
+        assert_eq!(list[2].date, Some("2024-08-28 12:00".parse().unwrap()));
+        assert_eq!(list[2].repeat.to_string(), "FREQ=DAILY");
+
+        // И выполнение родителя по подзадачам
+        let child = Task {
+            title: "Купить удобрение".into(),
+            parent: 3,
+            ..Task::default()
+        };
+        list.add(child).unwrap();
+        list.done("Купить удобрение".into()).unwrap();
+        assert_eq!(list.complete_parents(4), vec![3]);
+        assert!(list[2].repeat.is_none());
+        assert_eq!(list.len(), 5);
+        assert_eq!(list[4].date, Some("2024-08-29 12:00".parse().unwrap()));
+
+        assert_eq!(
+            list.execute("update tasks set repeat = 'FREQ=SOMETIMES'"),
+            Err(Sql(SqlError::format(
+                "[.. 'FREQ=..;..' | '']",
+                "'FREQ=SOMETIMES'"
+            )))
+        );
+    }
+}
+
+#[cfg(test)]
+mod timezone {
+    use super::*;
+    use crate::clock;
+    use chrono_tz::Tz;
+
+    fn date(s: &str) -> Date {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let moscow = date("2024-08-20 12:00 Europe/Moscow");
+        assert_eq!(moscow.zone(), Some(Tz::Europe__Moscow));
+        assert_eq!(moscow, date("2024-08-20 11:00 Europe/Berlin"));
+        assert_eq!(moscow, date("2024-08-20T12:00:00+03:00"));
+        assert_eq!(moscow, date("2024-08-20 09:00Z"));
+        assert_eq!(moscow, date("2024-08-20 12:00+03"));
+        assert_eq!(moscow, date("2024-08-20T12:00:00+03:00[Europe/Moscow]"));
+        assert!(moscow < date("2024-08-20 12:00 Europe/Berlin"));
+
+        // Пользователь в UTC видит время по UTC, в Москве - по Москве
+        assert_eq!(moscow.to_string(), "2024-08-20 09:00");
+        clock::with_zone(Tz::Europe__Moscow, || {
+            assert_eq!(moscow.to_string(), "2024-08-20 12:00");
+            assert_eq!(date("2024-08-20 12:00"), moscow);
+        });
+
+        assert!("2024-08-20 12:00 Mars/Olympus".parse::<Date>().is_err());
+        assert!("2024-08-20 12:00 [Europe/Moscow".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn json() {
+        // Даты без пояса из старого tasks.json читаются как местное время пользователя
+        let list = clock::with_zone(Tz::Europe__Moscow, || {
+            serde_json::from_str::<List>(
+                r#"[
+                    {"title": "Отчет", "descr": "", "date": "2024-08-20T12:00:00", "category": "", "is_done": false},
+                    {"title": "Звонок", "descr": "", "date": "2024-08-20T11:00:00+02:00[Europe/Berlin]", "category": "", "is_done": false}
+                ]"#,
+            )
+            .unwrap()
+        });
+        assert_eq!(list[0].date, list[1].date);
+        assert_eq!(
+            serde_json::to_value(&list[0].date).unwrap(),
+            serde_json::json!("2024-08-20T09:00:00Z")
+        );
+        assert_eq!(
+            serde_json::to_value(&list[1].date).unwrap(),
+            serde_json::json!("2024-08-20T11:00:00+02:00[Europe/Berlin]")
+        );
+
+        assert_eq!(
+            list.select("select * where date = '2024-08-20 12:00 Europe/Moscow'")
+                .map(|list| list.len()),
+            Ok(2)
+        );
+        assert_eq!(
+            list.select("select * where date < '2024-08-20 10:00 Europe/Berlin'")
+                .map(|list| list.len()),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn recurrence() {
+        // Еженедельная задача в 09:00 по Берлину остается в 09:00 после перехода на зимнее время
+        let repeat = "FREQ=WEEKLY".parse::<Recurrence>().unwrap();
+        let (next, _) = repeat
+            .next(&date("2024-10-21 09:00 Europe/Berlin"))
+            .unwrap();
+        assert_eq!(next.zone(), Some(Tz::Europe__Berlin));
+        assert_eq!(next, date("2024-10-28 08:00Z"));
+    }
+}
+
+#[cfg(test)]
+mod all_day {
+    use super::*;
+    use crate::clock;
+    use chrono::NaiveDate;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn parse() {
+        let day = "2024-08-20".parse::<Date>().unwrap();
+        assert!(day.is_all_day());
+        assert_eq!(day.to_string(), "2024-08-20");
+        assert_eq!(
+            serde_json::to_value(&day).unwrap(),
+            serde_json::json!("2024-08-20")
+        );
+        assert_eq!(
+            serde_json::from_value::<Date>(serde_json::json!("2024-08-20")).unwrap(),
+            day
+        );
+
+        // День и полночь - разные сроки, день сортируется раньше
+        let midnight = "2024-08-20 00:00".parse::<Date>().unwrap();
+        assert_ne!(day, midnight);
+        assert!(day < midnight);
+        assert_eq!(day.compare(&midnight), Ordering::Equal);
+        assert_eq!(
+            day.compare(&"2024-08-21 00:00".parse().unwrap()),
+            Ordering::Less
+        );
+
+        assert_eq!(
+            Task::format_by_key("date", "\"2024-08-20\"".into()),
+            "2024-08-20"
+        );
+    }
+
+    #[test]
+    fn select() {
+        let task = |title: &str, date: &str| Task {
+            title: title.into(),
+            date: Some(date.parse().unwrap()),
+            ..Task::default()
+        };
+        let list = List::from(vec![
+            task("Отчет", "2024-08-20"),
+            task("Созвон", "2024-08-20 12:00"),
+            task("Уборка", "2024-08-19 23:00"),
+            task("Отпуск", "2024-08-21"),
+        ]);
+        let now = NaiveDate::from_ymd_opt(2024, 8, 20)
+            .unwrap()
+            .and_hms_opt(0, 1, 0)
+            .unwrap();
+        let select = |sql| {
+            let found = clock::with_now(now, || list.select(sql)).unwrap();
+            titles(&found).join(", ")
+        };
+
+        // Задача на весь день не просрочена до конца дня
+        assert_eq!(select("select * where date < now()"), "Уборка");
+        assert_eq!(
+            select("select * where date >= current_date"),
+            "Отчет, Созвон, Отпуск"
+        );
+
+        // День равен любому моменту внутри него
+        assert_eq!(
+            select("select * where date = '2024-08-20'"),
+            "Отчет, Созвон"
+        );
+        assert_eq!(select("select * where date = '2024-08-20 18:00'"), "Отчет");
+        assert_eq!(select("select * where date > '2024-08-20'"), "Отпуск");
+        assert_eq!(
+            select("select * where date <= '2024-08-20'"),
+            "Отчет, Созвон, Уборка"
+        );
+        assert_eq!(
+            select("select * where date between '2024-08-20 13:00' and '2024-08-21 00:00'"),
+            "Отчет, Отпуск"
+        );
+        assert_eq!(
+            select("select * where date in ('2024-08-21', '2024-08-19 23:00')"),
+            "Уборка, Отпуск"
+        );
+        assert_eq!(
+            select("select * order by date desc"),
+            "Отпуск, Созвон, Отчет, Уборка"
+        );
+    }
+
+    #[test]
+    fn recurrence() {
+        let repeat = "FREQ=MONTHLY".parse::<Recurrence>().unwrap();
+        let (next, _) = repeat.next(&"2024-01-31".parse().unwrap()).unwrap();
+        assert!(next.is_all_day());
+        assert_eq!(next.to_string(), "2024-03-31");
+    }
+}
+
+#[cfg(test)]
+mod natural {
+    use super::*;
+    use crate::clock;
+
+    // Вторник, 2024-08-20 10:30
+    fn parse(s: &str) -> Result<String, String> {
+        let now = "2024-08-20 10:30".parse::<Date>().unwrap().datetime();
+        clock::with_now(now, || Date::parse_natural(s)).map(|date| date.to_string())
+    }
+
+    #[test]
+    fn english() {
+        assert_eq!(parse("2024-08-22 12:00"), Ok("2024-08-22 12:00".into()));
+        assert_eq!(parse("today"), Ok("2024-08-20".into()));
+        assert_eq!(parse("Tomorrow 18:00"), Ok("2024-08-21 18:00".into()));
+        assert_eq!(parse("tomorrow at 9:15"), Ok("2024-08-21 09:15".into()));
+        assert_eq!(parse("18:00"), Ok("2024-08-20 18:00".into()));
+        assert_eq!(parse("next friday"), Ok("2024-08-23".into()));
+        assert_eq!(parse("tuesday"), Ok("2024-08-27".into()));
+        assert_eq!(parse("in 3 days"), Ok("2024-08-23".into()));
+        assert_eq!(parse("in a week 12:00"), Ok("2024-08-27 12:00".into()));
+        assert_eq!(parse("in 2 hours"), Ok("2024-08-20 12:30".into()));
+        assert_eq!(parse("next month"), Ok("2024-09-20".into()));
+        assert_eq!(parse("end of month"), Ok("2024-08-31".into()));
+        assert_eq!(parse("end of week 20:00"), Ok("2024-08-25 20:00".into()));
+        assert_eq!(parse("end of year"), Ok("2024-12-31".into()));
+    }
+
+    #[test]
+    fn russian() {
+        assert_eq!(parse("сегодня"), Ok("2024-08-20".into()));
+        assert_eq!(parse("Завтра в 18:00"), Ok("2024-08-21 18:00".into()));
+        assert_eq!(parse("послезавтра"), Ok("2024-08-22".into()));
+        assert_eq!(parse("в пятницу"), Ok("2024-08-23".into()));
+        assert_eq!(parse("в следующий понедельник"), Ok("2024-08-26".into()));
+        assert_eq!(parse("через 2 недели"), Ok("2024-09-03".into()));
+        assert_eq!(parse("через 5 дней"), Ok("2024-08-25".into()));
+        assert_eq!(parse("через час"), Ok("2024-08-20 11:30".into()));
+        assert_eq!(parse("через 30 минут"), Ok("2024-08-20 11:00".into()));
+        assert_eq!(parse("конец месяца"), Ok("2024-08-31".into()));
+        assert_eq!(parse("в конце года"), Ok("2024-12-31".into()));
+    }
+
+    #[test]
+    fn errors() {
+        for s in [
+            "",
+            "someday",
+            "in many days",
+            "in 2 hours 18:00",
+            "next fortnight",
+            "через пару дней",
+            "friday friday",
+            "25:00",
+            "in 4000000000 days",
+            "in 4000000000 hours",
+            "через 4000000000 недель",
+        ] {
+            assert!(parse(s).is_err(), "{}", s);
+        }
+
+        // update принимает ту же запись
+        let mut task = Task::default();
+        let now = "2024-08-20 10:30".parse::<Date>().unwrap().datetime();
+        clock::with_now(now, || task.change_by_key("date", "завтра 9:00")).unwrap();
+        assert_eq!(task.date, Some("2024-08-21 09:00".parse().unwrap()));
+        assert!(task.change_by_key("date", "someday").is_err());
+    }
+}
+
+#[cfg(test)]
+mod optional {
+    use super::*;
+    use command::Output::*;
+    use serde_json::json;
+
+    #[test]
+    fn is_null() {
+        let list = List::from(vec![
+            task("Отчет", "Работа", "2024-08-20 12:00"),
+            Task {
+                title: "Идея".into(),
+                ..Task::default()
+            },
+            Task {
+                category: None,
+                ..task("Созвон", "", "2024-08-19 12:00")
+            },
+        ]);
+        let select = |sql| list.select(sql).map(|list| titles(&list).join(", "));
+        assert_eq!(select("select * where date is null"), Ok("Идея".into()));
+        assert_eq!(
+            select("select * where date is not null"),
+            Ok("Отчет, Созвон".into())
+        );
+        assert_eq!(
+            select("select * where category is null"),
+            Ok("Идея, Созвон".into())
+        );
+        // Сравнение с отсутствующим значением не выполняется ни в одну сторону
+        assert_eq!(
+            select("select * where date < \"2024-09-01 00:00\" or date >= \"2024-09-01 00:00\""),
+            Ok("Отчет, Созвон".into())
+        );
+        assert_eq!(
+            select("select * where category in ('Работа', 'Дом') or category like '%'"),
+            Ok("Отчет".into())
+        );
+
+        // NOT неизвестного результата тоже неизвестен
+        assert_eq!(
+            select("select * where not category = 'Дом'"),
+            Ok("Отчет".into())
+        );
+        assert_eq!(
+            select("select * where not category like 'Р%'"),
+            Ok("".into())
+        );
+        assert_eq!(
+            select("select * where not (category = 'Работа' and date is null)"),
+            Ok("Отчет, Созвон".into())
+        );
+        assert_eq!(
+            select("select * where not (category = 'Дом' or date is null)"),
+            Ok("Отчет".into())
+        );
+        let explain = list.explain("select * where not category = 'Дом'").unwrap();
+        assert_eq!(
+            explain
+                .steps
+                .iter()
+                .map(|step| (step.input, step.output))
+                .collect::<Vec<_>>(),
+            vec![(3, 1), (3, 0)]
+        );
+        let mut list = list.clone();
+        assert_eq!(
+            list.execute("delete from tasks where not category = 'Дом'"),
+            Ok(Deleted(1))
+        );
+        assert_eq!(titles(&list), vec!["Идея", "Созвон"]);
+    }
+
+    #[test]
+    fn order() {
+        let list = List::from(vec![
+            task("Отчет", "Работа", "2024-08-20 12:00"),
+            Task {
+                title: "Идея".into(),
+                ..Task::default()
+            },
+            Task {
+                category: None,
+                ..task("Созвон", "", "2024-08-19 12:00")
+            },
+        ]);
+        let select = |sql| list.select(sql).map(|list| titles(&list).join(", "));
+        // Без даты - в конце при любом направлении
+        assert_eq!(
+            select("select * order by date"),
+            Ok("Созвон, Отчет, Идея".into())
+        );
+        assert_eq!(
+            select("select * order by date desc"),
+            Ok("Отчет, Созвон, Идея".into())
+        );
+        assert_eq!(
+            select("select * order by date desc nulls first"),
+            Ok("Идея, Отчет, Созвон".into())
+        );
+        assert_eq!(
+            select("select * order by date nulls last"),
+            Ok("Созвон, Отчет, Идея".into())
+        );
+    }
+
+    #[test]
+    fn aggregates() {
+        let list = List::from(vec![
+            task("Отчет", "Работа", "2024-08-20 12:00"),
+            Task {
+                title: "Идея".into(),
+                ..Task::default()
+            },
+            Task {
+                category: None,
+                ..task("Созвон", "", "2024-08-19 12:00")
+            },
+        ]);
+        assert_eq!(
+            list.query("select count(*), count(date), count(category), min(date)")
+                .unwrap()
+                .rows,
+            vec![vec![
+                json!(3),
+                json!(2),
+                json!(1),
+                json!("2024-08-19T12:00:00Z")
+            ]]
+        );
+    }
+
+    #[test]
+    fn execute() {
+        let mut list = List::from(vec![
+            task("Отчет", "Работа", "2024-08-20 12:00"),
+            Task {
+                title: "Идея".into(),
+                ..Task::default()
+            },
+            Task {
+                category: None,
+                ..task("Созвон", "", "2024-08-19 12:00")
+            },
+        ]);
+        assert_eq!(
+            list.execute("insert into tasks (title) values ('Мечта')"),
+            Ok(Inserted(1))
+        );
+        assert_eq!(list.get_task("Мечта").unwrap().date, None);
+
+        assert_eq!(
+            list.execute("update tasks set date = null, category = null where title = 'Отчет'"),
+            Ok(Updated(1))
+        );
+        let task = list.get_task("Отчет").unwrap();
+        assert_eq!((&task.date, &task.category), (&None, &None));
+        assert_eq!(
+            list.select("select * where date is null")
+                .map(|list| list.len()),
+            Ok(3)
+        );
+
+        // Название обязательно
+        assert_eq!(
+            list.execute("update tasks set title = null"),
+            Err(Sql(SqlError::format("[.. StringValue]", "NULL")))
+        );
+
+        // Очистка из CLI
+        let mut task = list.get_task("Созвон").unwrap().clone();
+        task.change_by_key("descr", "Созвон с клиентом").unwrap();
+        assert_eq!(task.descr.as_deref(), Some("Созвон с клиентом"));
+        for key in ["descr", "date", "category"] {
+            task.change_by_key(key, "-").unwrap();
+        }
+        assert_eq!((task.descr, task.date, task.category), (None, None, None));
+    }
+
+    #[test]
+    fn json() {
+        // Пустые строки из старых файлов читаются как отсутствие значения
+        let task: Task = serde_json::from_value(json!({
+            "title": "Идея",
+            "descr": "",
+            "category": "",
+            "is_done": false,
+        }))
+        .unwrap();
+        assert_eq!((task.descr, task.date, task.category), (None, None, None));
+
+        let value = serde_json::to_value(Task {
+            title: "Идея".into(),
+            ..Task::default()
+        })
+        .unwrap();
+        assert_eq!(value["date"], serde_json::Value::Null);
+        assert_eq!(
+            Task::get_keys()
+                .iter()
+                .filter(|key| Task::is_optional(key))
+                .collect::<Vec<_>>(),
+            vec!["descr", "date", "category"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod status {
+    use super::*;
+    use crate::list::planner::{Lookup, Plan};
+    use command::Output::*;
+    use serde_json::json;
+    use workflow::Workflow;
+
+    #[test]
+    fn transitions() {
+        let task = |id, title: &str| Task {
+            id,
+            title: title.into(),
+            ..Task::default()
+        };
+        let mut list = List::from(vec![task(1, "Отчет"), task(2, "Созвон"), task(3, "Уборка")]);
+        let status = |list: &mut List, key: &str, status| {
+            list.set_status(key.into(), status).map(|task| task.status)
+        };
+
+        assert_eq!(
+            status(&mut list, "Отчет", Status::InProgress),
+            Ok(Status::InProgress)
+        );
+        assert_eq!(
+            status(&mut list, "Отчет", Status::Waiting),
+            Ok(Status::Waiting)
+        );
+        assert_eq!(
+            list.done("Отчет".into()).map(|task| task.status),
+            Ok(Status::Done)
+        );
+
+        // Из закрытого статуса - только обратно в todo
+        assert_eq!(
+            status(&mut list, "Отчет", Status::InProgress),
+            Err(IllegalTransition(Status::Done, Status::InProgress))
+        );
+        assert_eq!(list.done("Отчет".into()), Err(TaskAlreadyCompleted));
+        assert_eq!(status(&mut list, "Отчет", Status::Todo), Ok(Status::Todo));
+        assert_eq!(
+            status(&mut list, "Отчет", Status::Todo),
+            Err(TaskAlreadyInStatus(Status::Todo))
+        );
+
+        assert_eq!(
+            status(&mut list, "Созвон", Status::Cancelled),
+            Ok(Status::Cancelled)
+        );
+        assert_eq!(
+            list.done("Созвон".into()),
+            Err(IllegalTransition(Status::Cancelled, Status::Done))
+        );
+        assert_eq!(
+            status(&mut list, "Нет такой", Status::Done),
+            Err(TaskNotExist("Нет такой".into()))
+        );
+
+        // update проверяет статус так же
+        let mut task = list.get_task("Созвон").unwrap().clone();
+        task.change_by_key("status", "waiting").unwrap();
+        assert_eq!(
+            list.update("Созвон".into(), &task),
+            Err(IllegalTransition(Status::Cancelled, Status::Waiting))
+        );
+        task.status = Status::Todo;
+        assert_eq!(
+            list.update("Созвон".into(), &task).map(|task| task.status),
+            Ok(Status::Todo)
+        );
+        assert_eq!(*list.index(), Index::new(&list));
+    }
+
+    #[test]
+    fn custom_workflow() {
+        let task = |id, title: &str| Task {
+            id,
+            title: title.into(),
+            ..Task::default()
+        };
+        let mut list = List::from(vec![task(1, "Отчет"), task(2, "Созвон"), task(3, "Уборка")]);
+        // Выполнить можно только начатую задачу, отменить - нельзя вовсе
+        let workflow = serde_json::from_value::<Workflow>(json!({
+            "todo": ["in_progress"],
+            "in_progress": ["todo", "done"],
+            "done": ["todo"],
+        }))
+        .unwrap();
+        list.set_workflow(workflow);
+
+        assert_eq!(
+            list.done("Отчет".into()),
+            Err(IllegalTransition(Status::Todo, Status::Done))
+        );
+        assert_eq!(
+            list.set_status("Отчет".into(), Status::Cancelled),
+            Err(IllegalTransition(Status::Todo, Status::Cancelled))
+        );
+        list.set_status("Отчет".into(), Status::InProgress).unwrap();
+        assert_eq!(
+            list.done("Отчет".into()).map(|task| task.status),
+            Ok(Status::Done)
+        );
+
+        assert!(Workflow::default().allows(Status::Deferred, Status::Done));
+        assert!(!Workflow::default().allows(Status::Cancelled, Status::Waiting));
+    }
+
+    #[test]
+    fn dependencies() {
+        let task = |id, title: &str| Task {
+            id,
+            title: title.into(),
+            ..Task::default()
+        };
+        let mut list = List::from(vec![task(1, "Отчет"), task(2, "Созвон"), task(3, "Уборка")]);
+        list.depend("Отчет".into(), &["Созвон".into()]).unwrap();
+
+        // Начать и выполнить заблокированную задачу нельзя, отложить - можно
+        assert_eq!(
+            list.set_status("Отчет".into(), Status::InProgress),
+            Err(TaskBlocked(vec![2]))
+        );
+        assert_eq!(list.done("Отчет".into()), Err(TaskBlocked(vec![2])));
+        assert!(list.set_status("Отчет".into(), Status::Deferred).is_ok());
+
+        // Отмененная зависимость не блокирует
+        list.set_status("Созвон".into(), Status::Cancelled).unwrap();
+        assert!(!list.is_blocked(list.get_task("Отчет").unwrap()));
+        assert_eq!(
+            list.next()
+                .iter()
+                .map(|task| task.title.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Отчет", "Уборка"]
+        );
+
+        // Отмененная подзадача считается закрытой
+        let mut list = List::from(vec![]);
+        let parent = list.add(Task::default()).unwrap().id;
+        for title in ["a", "b"] {
+            list.add(Task {
+                title: title.into(),
+                parent,
+                ..Task::default()
+            })
+            .unwrap();
+        }
+        list.set_status("a".into(), Status::Cancelled).unwrap();
+        list.done("b".into()).unwrap();
+        assert_eq!(list.progress(parent), (2, 2));
+        assert_eq!(list.complete_parents(3), vec![parent]);
+    }
+
+    #[test]
+    fn json() {
+        // Старые файлы: is_done вместо статуса
+        let tasks: Vec<Task> = serde_json::from_value(json!([
+            { "title": "a", "is_done": true },
+            { "title": "b", "is_done": false },
+            { "title": "c", "status": "in_progress" },
+        ]))
+        .unwrap();
+        assert_eq!(
+            tasks.iter().map(|task| task.status).collect::<Vec<_>>(),
+            vec![Status::Done, Status::Todo, Status::InProgress]
+        );
+
+        let value = serde_json::to_value(&tasks[2]).unwrap();
+        assert_eq!(value["status"], json!("in_progress"));
+        assert_eq!(value.get("is_done"), None);
+
+        assert!(
+            serde_json::from_value::<Task>(json!({ "title": "d", "status": "started" })).is_err()
+        );
+        assert_eq!("Cancelled".parse(), Ok(Status::Cancelled));
+    }
+
+    #[test]
+    fn sql() {
+        let task = |id, title: &str| Task {
+            id,
+            title: title.into(),
+            ..Task::default()
+        };
+        let mut list = List::from(vec![task(1, "Отчет"), task(2, "Созвон"), task(3, "Уборка")]);
+        list.set_status("Отчет".into(), Status::InProgress).unwrap();
+        list.done("Созвон".into()).unwrap();
+        let select = |list: &List, sql| list.select(sql).map(|list| titles(&list).join(", "));
+
+        assert_eq!(
+            select(&list, "select * where status = 'in_progress'"),
+            Ok("Отчет".into())
+        );
+        assert_eq!(
+            select(&list, "select * where status in ('todo', 'done')"),
+            Ok("Созвон, Уборка".into())
+        );
+        assert_eq!(
+            select(&list, "select * order by status desc"),
+            Ok("Созвон, Отчет, Уборка".into())
+        );
+        assert_eq!(
+            list.select("select * where status = 'started'"),
+            Err(Sql(SqlError::format(
+                "[.. 'todo' | 'in_progress' | 'waiting' | 'deferred' | 'done' | 'cancelled']",
+                "'started'"
+            )))
+        );
+        assert_eq!(
+            list.select("select * where status > 'todo'"),
+            Err(Sql(SqlError::format("status = ..", ">")))
+        );
+        assert_eq!(
+            Plan::new(
+                &list,
+                "select * where status = 'done'"
+                    .parse::<SelectQuery>()
+                    .unwrap()
+                    .condition
+                    .as_ref()
+            ),
+            Plan::Index {
+                lookup: Lookup::Status(vec![Status::Done]),
+                estimate: 1
+            }
+        );
+
+        // is_done из старых запросов вычисляется по статусу
+        assert_eq!(
+            select(&list, "select * where is_done = false"),
+            Ok("Отчет, Уборка".into())
+        );
+        assert_eq!(
+            list.query("select title, is_done order by is_done desc")
+                .unwrap()
+                .rows,
+            vec![
+                vec![json!("Созвон"), json!(true)],
+                vec![json!("Отчет"), json!(false)],
+                vec![json!("Уборка"), json!(false)],
+            ]
+        );
+
+        // Запросы меняют статус по правилам workflow
+        assert_eq!(
+            list.execute("update tasks set status = 'waiting' where title = 'Уборка'"),
+            Ok(Updated(1))
+        );
+        assert_eq!(list.get_task("Уборка").unwrap().status, Status::Waiting);
+        assert_eq!(
+            list.execute("update tasks set is_done = false"),
+            Ok(Updated(3))
+        );
+        assert_eq!(
+            list.iter().map(|task| task.status).collect::<Vec<_>>(),
+            vec![Status::InProgress, Status::Todo, Status::Waiting]
+        );
+        assert_eq!(
+            List::prepare("select * where status = ?")
+                .unwrap()
+                .select(&list, &["waiting".into()])
+                .map(|list| list.len()),
+            Ok(1)
+        );
+        list.set_status("Уборка".into(), Status::Cancelled).unwrap();
+        assert_eq!(
+            list.execute("update tasks set status = 'in_progress' where title = 'Уборка'"),
+            Err(IllegalTransition(Status::Cancelled, Status::InProgress))
+        );
+        assert_eq!(
+            list.execute("update tasks set is_done = true"),
+            Err(IllegalTransition(Status::Cancelled, Status::Done))
+        );
+        assert_eq!(
+            list.iter().map(|task| task.status).collect::<Vec<_>>(),
+            vec![Status::InProgress, Status::Todo, Status::Cancelled]
         );
+        assert_eq!(*list.index(), Index::new(&list));
     }
 }
@@ -0,0 +1,112 @@
+use super::*;
+use std::collections::HashSet;
+
+impl List {
+    // Прямые подзадачи в порядке списка
+    pub fn children(&self, id: u64) -> impl DoubleEndedIterator<Item = &Task> {
+        self.index
+            .children(id)
+            .iter()
+            .map(move |pos| &self.tasks[*pos])
+    }
+
+    // Номер задачи и всех ее подзадач на любой глубине
+    pub fn subtree(&self, id: u64) -> Vec<u64> {
+        let mut ids = Vec::new();
+        if self.index.id(id).is_none() {
+            return ids;
+        }
+        // Повторный номер - цикл в файле, дальше не идем
+        let mut seen = HashSet::new();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            if seen.insert(id) {
+                ids.push(id);
+                stack.extend(self.children(id).map(|task| task.id).rev());
+            }
+        }
+        ids
+    }
+
+    // Закрытые (выполненные или отмененные) и все прямые подзадачи
+    pub fn progress(&self, id: u64) -> (usize, usize) {
+        let children = self.index.children(id);
+        let done = children
+            .iter()
+            .filter(|pos| self.tasks[**pos].status.is_closed())
+            .count();
+        (done, children.len())
+    }
+
+    // Родитель должен существовать и не быть самой задачей или ее подзадачей
+    pub(crate) fn check_parent(&self, task: &Task) -> Result<(), ListError> {
+        if task.parent == 0 {
+            Ok(())
+        } else if self.index.id(task.parent).is_none() {
+            Err(ParentNotExist(task.parent))
+        } else if task.id != 0 && self.subtree(task.id).contains(&task.parent) {
+            Err(CyclicParent(task.id))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Выполняет родителей задачи вверх по дереву, пока у них закрыты все подзадачи,
+    // нет невыполненных зависимостей и workflow разрешает переход в done
+    pub fn complete_parents(&mut self, id: u64) -> Vec<u64> {
+        let mut completed = Vec::new();
+        let mut parent = self.index.id(id).map_or(0, |pos| self.tasks[pos].parent);
+        while let Some(pos) = self.index.id(parent) {
+            let (done, total) = self.progress(parent);
+            let task = Task {
+                status: Status::Done,
+                ..self.tasks[pos].clone()
+            };
+            if done < total
+                || self
+                    .check_transition(self.tasks[pos].status, &task)
+                    .is_err()
+            {
+                break;
+            }
+            let task = self.complete(task);
+            completed.push(parent);
+            parent = self.replace(pos, task).parent;
+        }
+        completed
+    }
+
+    // Позиции задач в порядке дерева и их глубина. Подзадачи, чьего родителя
+    // нет в списке (например, в выборке), выводятся на верхнем уровне
+    pub fn tree(&self) -> Vec<(usize, usize)> {
+        let mut order = Vec::with_capacity(self.len());
+        let mut seen = vec![false; self.len()];
+        let roots = (0..self.len())
+            .filter(|pos| self.index.id(self.tasks[*pos].parent).is_none())
+            // Задачи из цикла в файле тоже выводятся
+            .chain(0..self.len())
+            .collect::<Vec<_>>();
+
+        for root in roots {
+            let mut stack = vec![(root, 0)];
+            while let Some((pos, depth)) = stack.pop() {
+                if seen[pos] {
+                    continue;
+                }
+                seen[pos] = true;
+                order.push((pos, depth));
+                let id = self.tasks[pos].id;
+                if id != 0 {
+                    stack.extend(
+                        self.index
+                            .children(id)
+                            .iter()
+                            .rev()
+                            .map(|child| (*child, depth + 1)),
+                    );
+                }
+            }
+        }
+        order
+    }
+}
@@ -0,0 +1,43 @@
+use super::*;
+use std::collections::BTreeMap;
+
+// Именованные SELECT запросы, хранятся рядом с tasks.json
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Views(BTreeMap<String, String>);
+
+impl Display for Views {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut table = prettytable::Table::new();
+        table.add_row(["name", "query"].iter().collect());
+        for (name, sql) in &self.0 {
+            table.add_row([name, sql].iter().collect());
+        }
+        write!(f, "{}", table)
+    }
+}
+
+impl Views {
+    // Запрос проверяется тем же разбором, что и в List::select, поэтому
+    // сломанное представление не сохранится. Существующее заменяется
+    pub fn save(&mut self, name: &str, sql: &str) -> Result<(), ListError> {
+        sql.parse::<SelectQuery>()?;
+        self.0.insert(name.into(), sql.into());
+        Ok(())
+    }
+    pub fn get(&self, name: &str) -> Result<&str, ListError> {
+        self.0
+            .get(name)
+            .map(String::as_str)
+            .ok_or(ViewNotExist(name.into()))
+    }
+    pub fn delete(&mut self, name: &str) -> Result<String, ListError> {
+        self.0.remove(name).ok_or(ViewNotExist(name.into()))
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.0.iter()
+    }
+    // Строки результата с учетом проекции и агрегатов запроса
+    pub fn run(&self, name: &str, list: &List) -> Result<Rows, ListError> {
+        list.query(self.get(name)?)
+    }
+}
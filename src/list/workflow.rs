@@ -0,0 +1,110 @@
+use super::*;
+use std::collections::BTreeMap;
+
+// Разрешенные переходы между статусами: из статуса - в какие можно перейти.
+// Хранится рядом с tasks.json в workflow.json, без файла - переходы по умолчанию.
+// Статус без записи - конечный, из него перейти нельзя
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Workflow(BTreeMap<Status, Vec<Status>>);
+
+// Незакрытая задача переходит в любой статус, выполненная и отмененная - только обратно в todo
+impl Default for Workflow {
+    fn default() -> Self {
+        Self(
+            Status::ALL
+                .iter()
+                .map(|from| {
+                    let to = if from.is_closed() {
+                        vec![Status::Todo]
+                    } else {
+                        Status::ALL
+                            .iter()
+                            .copied()
+                            .filter(|to| to != from)
+                            .collect()
+                    };
+                    (*from, to)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Workflow {
+    pub fn allows(&self, from: Status, to: Status) -> bool {
+        self.0
+            .get(&from)
+            .is_some_and(|allowed| allowed.contains(&to))
+    }
+}
+
+impl List {
+    pub fn workflow(&self) -> &Workflow {
+        &self.workflow
+    }
+
+    pub fn set_workflow(&mut self, workflow: Workflow) {
+        self.workflow = workflow;
+    }
+
+    // Переводит задачу в статус status по правилам workflow. Начать и выполнить
+    // можно только задачу без невыполненных зависимостей. Выполненная повторяющаяся
+    // задача порождает следующую, а сама перестает повторяться
+    pub fn set_status(&mut self, key: String, status: Status) -> Result<&Task, ListError> {
+        let pos = self.position(&key)?;
+        let task = &self.tasks[pos];
+
+        if task.status == status {
+            return Err(match status {
+                Status::Done => TaskAlreadyCompleted,
+                status => TaskAlreadyInStatus(status),
+            });
+        }
+        let mut task = Task {
+            status,
+            ..task.clone()
+        };
+        self.check_transition(self.tasks[pos].status, &task)?;
+        if status == Status::Done {
+            task = self.complete(task);
+        }
+        Ok(self.replace(pos, task))
+    }
+
+    // Проверяет смену статуса from на статус task. Общая часть для set_status, update и UPDATE
+    pub(crate) fn check_transition(&self, from: Status, task: &Task) -> Result<(), ListError> {
+        if !self.workflow.allows(from, task.status) {
+            return Err(IllegalTransition(from, task.status));
+        }
+        let blockers = self.blockers(task);
+        if matches!(task.status, Status::InProgress | Status::Done) && !blockers.is_empty() {
+            return Err(TaskBlocked(blockers));
+        }
+        Ok(())
+    }
+
+    // Добавляет следующее повторение выполненной задачи и возвращает ее без повторения.
+    // Общая часть для done, выполнения родителей и UPDATE
+    pub(crate) fn complete(&mut self, task: Task) -> Task {
+        // Без срока повторения считаются от сегодняшнего дня
+        let date = task
+            .date
+            .clone()
+            .unwrap_or_else(|| Date::day(crate::clock::now().date(), None));
+        if let Some((date, repeat)) = task.repeat.next(&date) {
+            let next = Task {
+                id: 0,
+                date: Some(date),
+                status: Status::Todo,
+                repeat,
+                ..task.clone()
+            };
+            // Такое повторение уже есть в списке - второе не нужно
+            let _ = self.add(next);
+        }
+        Task {
+            repeat: Recurrence::default(),
+            ..task
+        }
+    }
+}
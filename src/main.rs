@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests;
 
-use clap::{Arg, ArgMatches, Command};
+use clap::{Arg, ArgAction, ArgMatches, Command};
 use crossterm::style::Stylize;
 use std::{
     collections::VecDeque,
@@ -11,72 +11,228 @@ use std::{
 };
 use todo_list::{
     list::{
-        task::{Date, Task},
+        diagnostic,
+        search::SearchIndex,
+        task::{Date, Priority, Recurrence, Status, Task},
+        view::Views,
+        workflow::Workflow,
         List, ListError,
     },
     traits::Table,
 };
 
 fn main() {
-    let mut list = {
-        let tasks_json = fs::read_to_string("tasks.json").unwrap();
-        serde_json::from_str::<List>(&tasks_json).unwrap()
-    };
+    let tasks_json = fs::read_to_string("tasks.json").unwrap();
+    let mut list = serde_json::from_str::<List>(&tasks_json).unwrap();
+    let original_list = list.clone();
+
+    // Без файла с переходами статусов действуют переходы по умолчанию
+    if let Ok(workflow_json) = fs::read_to_string("workflow.json") {
+        list.set_workflow(serde_json::from_str::<Workflow>(&workflow_json).unwrap());
+    }
+
+    // Файла с представлениями может не быть, пока не сохранено ни одного
+    let mut views = fs::read_to_string("views.json")
+        .map(|views_json| serde_json::from_str::<Views>(&views_json).unwrap())
+        .unwrap_or_default();
 
     let matches = get_app().get_matches();
     let subcommand = matches.subcommand().unwrap();
+    let original_views = views.clone();
 
-    if let Err(e) = execute_command(subcommand, &mut list) {
+    if let Err(e) = execute_command(subcommand, &mut list, &mut views) {
         println!("{e}");
     }
 
-    if ["add", "done", "update", "delete"].contains(&subcommand.0) {
+    // Сохраняем только если команда изменила список (в т.ч. через SQL) или файл
+    // в старом формате: выданы номера, даты без пояса переписываются моментами UTC
+    if list != original_list || list.is_migrated() {
         fs::write("tasks.json", serde_json::to_string_pretty(&list).unwrap()).unwrap();
     }
+    if views != original_views {
+        fs::write("views.json", serde_json::to_string_pretty(&views).unwrap()).unwrap();
+    }
 }
 
 // clap-интерфейс
 fn get_app() -> Command {
-    let title = &Arg::new("title").help("Название задачи").required(true);
+    let title = &Arg::new("title")
+        .help("Номер или название задачи")
+        .required(true);
+    let tags = &Arg::new("tags").help("Теги").required(true).num_args(1..);
+    let depends_on = &Arg::new("depends_on")
+        .help("Номера или названия задач, которые нужно выполнить раньше")
+        .required(true)
+        .num_args(1..);
+    let view_name = &Arg::new("name").help("Название запроса").required(true);
 
     Command::new("ToDoList")
         .arg_required_else_help(true)
         .subcommand(
             Command::new("add")
                 .about("Добавляет новую задачу")
-                .arg(title)
-                .arg(Arg::new("descr").help("Описание задачи").required(true))
+                .arg(title.clone().help("Название задачи"))
+                // Описание, срок и категория необязательны, пустая строка - не задано
+                .arg(Arg::new("descr").help("Описание задачи"))
                 .arg(
                     Arg::new("date")
-                        .help("Срок выполнения задачи")
-                        .required(true)
+                        .help("Срок выполнения задачи: \"2024-08-20 12:00\", \"2024-08-20 12:00 Europe/Moscow\", \"tomorrow 18:00\", \"через 2 недели\"")
                         .value_parser(|date_str: &str| {
-                            date_str.parse::<Date>().map_err(|e| {
+                            if date_str.is_empty() {
+                                return Ok(None);
+                            }
+                            Date::parse_natural(date_str).map(Some).map_err(|e| {
                                 clap::Error::raw(clap::error::ErrorKind::InvalidValue, e)
                             })
                         }),
                 )
-                .arg(Arg::new("category").help("Категория задачи").required(true)),
+                .arg(Arg::new("category").help("Категория задачи"))
+                .arg(
+                    Arg::new("tags")
+                        .help("Тег задачи, можно указать несколько раз")
+                        .long("tag")
+                        .short('t')
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("parent")
+                        .help("Номер или название родительской задачи")
+                        .long("parent"),
+                )
+                .arg(
+                    Arg::new("priority")
+                        .help("Приоритет задачи: low, medium, high или urgent")
+                        .long("priority")
+                        .short('p')
+                        .default_value("medium")
+                        .value_parser(|priority: &str| {
+                            priority.parse::<Priority>().map_err(|e| {
+                                clap::Error::raw(clap::error::ErrorKind::InvalidValue, e)
+                            })
+                        }),
+                )
+                .arg(
+                    Arg::new("repeat")
+                        .help("Правило повторения RRULE, например FREQ=WEEKLY;BYDAY=MO,FR")
+                        .long("repeat")
+                        .short('r')
+                        .value_parser(|repeat: &str| {
+                            repeat.parse::<Recurrence>().map_err(|e| {
+                                clap::Error::raw(clap::error::ErrorKind::InvalidValue, e)
+                            })
+                        }),
+                ),
         )
         .subcommand(
             Command::new("done")
                 .about("Помечает задачу как выполненную")
+                .arg(title)
+                .arg(
+                    Arg::new("parents")
+                        .help("Также выполнить родителей, у которых выполнены все подзадачи")
+                        .long("parents")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(Command::new("start").about("Начинает работу над задачей").arg(title))
+        .subcommand(
+            Command::new("wait")
+                .about("Откладывает задачу до ответа или события")
+                .arg(title),
+        )
+        .subcommand(Command::new("defer").about("Переносит задачу на потом").arg(title))
+        .subcommand(Command::new("cancel").about("Отменяет задачу").arg(title))
+        .subcommand(
+            Command::new("reopen")
+                .about("Возвращает задачу в todo")
                 .arg(title),
         )
         .subcommand(
             Command::new("update")
                 .about("Обновляет существующую задачу")
-                .arg(title.clone().help("Название задачи для обновления")),
+                .arg(
+                    title
+                        .clone()
+                        .help("Номер или название задачи для обновления"),
+                ),
         )
         .subcommand(Command::new("delete").about("Удаляет задачу").arg(title))
         .subcommand(
-            Command::new("select").about("Отфильтровать список задач по определенному критерию"),
+            Command::new("tag")
+                .about("Теги задачи")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("Добавляет теги задаче")
+                        .arg(title)
+                        .arg(tags),
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("Убирает теги задачи")
+                        .arg(title)
+                        .arg(tags),
+                ),
+        )
+        .subcommand(
+            Command::new("depend")
+                .about("Зависимости задачи: задачи, которые нужно выполнить раньше")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("Добавляет зависимости задаче")
+                        .arg(title)
+                        .arg(depends_on),
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("Убирает зависимости задачи")
+                        .arg(title)
+                        .arg(depends_on),
+                ),
+        )
+        .subcommand(
+            Command::new("next")
+                .about("Невыполненные задачи в порядке зависимостей, доступные сейчас - первыми"),
+        )
+        .subcommand(
+            Command::new("select")
+                .about("Выполняет SQL запрос к списку задач: SELECT, INSERT, UPDATE или DELETE"),
+        )
+        .subcommand(
+            Command::new("search")
+                .about("Ищет задачи по словам в названии, категории и описании")
+                .arg(Arg::new("query").help("Слова для поиска").required(true)),
+        )
+        .subcommand(
+            Command::new("view")
+                .about("Сохраненные SELECT запросы")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("save")
+                        .about("Сохраняет запрос под именем")
+                        .arg(view_name)
+                        .arg(Arg::new("sql").help("SELECT запрос").required(true)),
+                )
+                .subcommand(
+                    Command::new("run")
+                        .about("Выполняет сохраненный запрос")
+                        .arg(view_name),
+                )
+                .subcommand(Command::new("list").about("Показывает сохраненные запросы"))
+                .subcommand(
+                    Command::new("delete")
+                        .about("Удаляет сохраненный запрос")
+                        .arg(view_name),
+                ),
         )
 }
 
 #[derive(Debug)]
 enum ExecuteError {
     ErrorsList(ListError),
+    // Ошибка SQL запроса с местом в тексте запроса
+    Query(String, ListError),
     String(&'static str),
 }
 
@@ -86,6 +242,16 @@ impl Display for ExecuteError {
         write!(f, "{}: ", "Error".red())?;
         match self {
             ErrorsList(e) => Display::fmt(e, f),
+            Query(sql, e) => {
+                Display::fmt(e, f)?;
+                match e {
+                    ListError::Sql(error) => match diagnostic::render(sql, error) {
+                        Some(rendered) => write!(f, "\n{}", rendered.red()),
+                        None => Ok(()),
+                    },
+                    _ => Ok(()),
+                }
+            }
             String(str) => Display::fmt(str, f),
         }
     }
@@ -94,39 +260,103 @@ impl Display for ExecuteError {
 fn execute_command(
     (command, sub_m): (&str, &ArgMatches),
     list: &mut List,
+    views: &mut Views,
 ) -> Result<(), ExecuteError> {
     match command {
         "add" => handle_add(sub_m, list),
         "done" => handle_done(sub_m, list),
+        "start" => handle_status(sub_m, list, Status::InProgress),
+        "wait" => handle_status(sub_m, list, Status::Waiting),
+        "defer" => handle_status(sub_m, list, Status::Deferred),
+        "cancel" => handle_status(sub_m, list, Status::Cancelled),
+        "reopen" => handle_status(sub_m, list, Status::Todo),
         "update" => handle_update(sub_m, list),
         "delete" => handle_delete(sub_m, list),
+        "tag" => handle_tag(sub_m.subcommand().unwrap(), list),
+        "depend" => handle_depend(sub_m.subcommand().unwrap(), list),
+        "next" => handle_next(list),
         "select" => handle_select(list),
+        "search" => handle_search(sub_m, list),
+        "view" => handle_view(sub_m.subcommand().unwrap(), list, views),
         _ => unreachable!(),
     }
 }
 
+// Значения всех --tag или аргументов tags
+fn tags(sub_m: &ArgMatches) -> Vec<String> {
+    sub_m
+        .get_many::<String>("tags")
+        .unwrap_or_default()
+        .cloned()
+        .collect()
+}
+
+// Необязательный аргумент: не указан или пустая строка - None
+fn optional(sub_m: &ArgMatches, id: &str) -> Option<String> {
+    sub_m
+        .get_one::<String>(id)
+        .filter(|value| !value.is_empty())
+        .cloned()
+}
+
 fn handle_add(sub_m: &ArgMatches, list: &mut List) -> Result<(), ExecuteError> {
-    let new_task = Task {
+    let mut new_task = Task {
+        id: 0,
         title: sub_m.get_one::<String>("title").unwrap().clone(),
-        descr: sub_m.get_one::<String>("descr").unwrap().clone(),
-        date: sub_m.get_one::<Date>("date").unwrap().clone(),
-        category: sub_m.get_one::<String>("category").unwrap().clone(),
-        is_done: false,
+        descr: optional(sub_m, "descr"),
+        date: sub_m.get_one::<Option<Date>>("date").cloned().flatten(),
+        category: optional(sub_m, "category"),
+        tags: Vec::new(),
+        status: Status::Todo,
+        priority: *sub_m.get_one::<Priority>("priority").unwrap(),
+        parent: match sub_m.get_one::<String>("parent") {
+            Some(parent) => list.get_task(parent).map_err(ExecuteError::ErrorsList)?.id,
+            None => 0,
+        },
+        depends_on: Vec::new(),
+        repeat: sub_m
+            .get_one::<Recurrence>("repeat")
+            .cloned()
+            .unwrap_or_default(),
     };
+    Task::add_tags(&mut new_task.tags, tags(sub_m).iter().map(String::as_str));
     list.add(new_task)
         .map(|added_task| {
             println!("Добавлена задача:\n{}", added_task);
         })
-        .map_err(|e| ExecuteError::ErrorsList(e))
+        .map_err(ExecuteError::ErrorsList)
 }
 
 fn handle_done(sub_m: &ArgMatches, list: &mut List) -> Result<(), ExecuteError> {
     let title = sub_m.get_one::<String>("title").unwrap().clone();
-    list.done(title)
-        .map(|done_task| {
-            println!("Задача выполнена:\n{}", done_task);
+    let len = list.len();
+    let done_task = list.done(title).map_err(ExecuteError::ErrorsList)?;
+    println!("Задача выполнена:\n{}", done_task);
+
+    let id = done_task.id;
+    if list.len() > len {
+        let next = list.iter().last().unwrap();
+        println!("Следующее повторение:\n{}", next);
+    }
+    if sub_m.get_flag("parents") {
+        for id in list.complete_parents(id) {
+            let parent = list.get_task(&id.to_string()).unwrap();
+            println!(
+                "Все подзадачи выполнены, родительская задача тоже:\n{}",
+                parent
+            );
+        }
+    }
+    Ok(())
+}
+
+fn handle_status(sub_m: &ArgMatches, list: &mut List, status: Status) -> Result<(), ExecuteError> {
+    let title = sub_m.get_one::<String>("title").unwrap().clone();
+    list.set_status(title, status)
+        .map(|task| {
+            println!("Статус задачи изменен:\n{}", task);
         })
-        .map_err(|e| ExecuteError::ErrorsList(e))
+        .map_err(ExecuteError::ErrorsList)
 }
 
 fn handle_update(sub_m: &ArgMatches, list: &mut List) -> Result<(), ExecuteError> {
@@ -135,16 +365,22 @@ fn handle_update(sub_m: &ArgMatches, list: &mut List) -> Result<(), ExecuteError
     // Находим задачу для изменения
     let mut new_task = list
         .get_task(&title)
-        .map_err(|e| ExecuteError::ErrorsList(e))?
+        .map_err(ExecuteError::ErrorsList)?
         .clone();
 
     let mut empty_field_count = 0;
     let mut is_again = false;
-    let task_entries = new_task.get_entries();
+    // id задачи не редактируется
+    let task_entries = new_task
+        .get_entries()
+        .to_vec()
+        .into_iter()
+        .filter(|(key, _)| key != "id")
+        .collect::<Vec<_>>();
     let mut queue = VecDeque::from(task_entries.clone());
 
     // Изменяем поля найденной задач
-    while let Some((key, value)) = queue.get(0) {
+    while let Some((key, value)) = queue.front() {
         // Интерактивный ввод значения полей
         let value = {
             let prompt = if is_again {
@@ -178,7 +414,7 @@ fn handle_update(sub_m: &ArgMatches, list: &mut List) -> Result<(), ExecuteError
             .map(|updated_task| {
                 println!("Задача обновлена:\n{}", updated_task);
             })
-            .map_err(|e| ExecuteError::ErrorsList(e))
+            .map_err(ExecuteError::ErrorsList)
     }
 }
 
@@ -188,18 +424,136 @@ fn handle_delete(sub_m: &ArgMatches, list: &mut List) -> Result<(), ExecuteError
         .map(|_| {
             println!("Задача удалена");
         })
-        .map_err(|e| ExecuteError::ErrorsList(e))
+        .map_err(ExecuteError::ErrorsList)
+}
+
+fn handle_tag((command, sub_m): (&str, &ArgMatches), list: &mut List) -> Result<(), ExecuteError> {
+    let title = sub_m.get_one::<String>("title").unwrap().clone();
+    match command {
+        "add" => list.tag(title, &tags(sub_m)),
+        "remove" => list.untag(title, &tags(sub_m)),
+        _ => unreachable!(),
+    }
+    .map(|tagged_task| {
+        println!("Теги изменены:\n{}", tagged_task);
+    })
+    .map_err(ExecuteError::ErrorsList)
+}
+
+fn handle_depend(
+    (command, sub_m): (&str, &ArgMatches),
+    list: &mut List,
+) -> Result<(), ExecuteError> {
+    let title = sub_m.get_one::<String>("title").unwrap().clone();
+    let depends_on = sub_m
+        .get_many::<String>("depends_on")
+        .unwrap()
+        .cloned()
+        .collect::<Vec<_>>();
+    match command {
+        "add" => list.depend(title, &depends_on),
+        "remove" => list.undepend(title, &depends_on),
+        _ => unreachable!(),
+    }
+    .map(|task| {
+        println!("Зависимости изменены:\n{}", task);
+    })
+    .map_err(ExecuteError::ErrorsList)
+}
+
+fn handle_next(list: &List) -> Result<(), ExecuteError> {
+    let tasks = list.next();
+    if tasks.is_empty() {
+        return Err(ExecuteError::String("Невыполненных задач нет"));
+    }
+    for (i, task) in tasks.iter().enumerate() {
+        let blockers = list.blockers(task);
+        if blockers.is_empty() {
+            println!("{}. {} ({})", i + 1, task.title.clone().green(), task.id);
+        } else {
+            let blockers = blockers
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!("{}. {} ({}), ждет: {blockers}", i + 1, task.title, task.id);
+        }
+    }
+    Ok(())
 }
 
 fn handle_select(list: &mut List) -> Result<(), ExecuteError> {
     let sql = interactive_input("Введите запрос: > ");
-    list.select(&sql)
-        .map_err(|e| ExecuteError::ErrorsList(e))
-        .map(|selected_list| {
-            println!("{}", selected_list);
+    list.execute(&sql)
+        .map_err(|e| ExecuteError::Query(sql.clone(), e))
+        .map(|output| {
+            println!("{}", output);
         })
 }
 
+fn handle_search(sub_m: &ArgMatches, list: &List) -> Result<(), ExecuteError> {
+    let query = sub_m.get_one::<String>("query").unwrap();
+    let index = SearchIndex::new(list);
+    let hits = index.search(list, query);
+    if hits.is_empty() {
+        return Err(ExecuteError::String("Ничего не найдено"));
+    }
+
+    let highlight = |text: &str, terms: &[String]| {
+        index
+            .tokenizer()
+            .highlight(text, terms, |word| word.yellow().bold().to_string())
+    };
+    for (i, hit) in hits.iter().enumerate() {
+        let task = hit.task;
+        println!(
+            "{}. {} [{}] {} ({:.2})",
+            i + 1,
+            highlight(&task.title, &hit.terms),
+            highlight(task.category.as_deref().unwrap_or_default(), &hit.terms),
+            task.date.as_ref().map(Date::to_string).unwrap_or_default(),
+            hit.score
+        );
+        if let Some(descr) = &task.descr {
+            println!("   {}", highlight(descr, &hit.terms));
+        }
+    }
+    Ok(())
+}
+
+fn handle_view(
+    (command, sub_m): (&str, &ArgMatches),
+    list: &List,
+    views: &mut Views,
+) -> Result<(), ExecuteError> {
+    let name = || sub_m.get_one::<String>("name").unwrap();
+    match command {
+        "save" => {
+            let sql = sub_m.get_one::<String>("sql").unwrap();
+            views
+                .save(name(), sql)
+                .map(|_| println!("Запрос \"{}\" сохранен", name()))
+                .map_err(|e| ExecuteError::Query(sql.clone(), e))
+        }
+        "run" => views
+            .run(name(), list)
+            .map(|rows| println!("{rows}"))
+            .map_err(|e| match views.get(name()) {
+                Ok(sql) => ExecuteError::Query(sql.into(), e),
+                Err(_) => ExecuteError::ErrorsList(e),
+            }),
+        "list" => {
+            println!("{views}");
+            Ok(())
+        }
+        "delete" => views
+            .delete(name())
+            .map(|_| println!("Запрос удален"))
+            .map_err(ExecuteError::ErrorsList),
+        _ => unreachable!(),
+    }
+}
+
 fn interactive_input(prompt: &str) -> String {
     print!("{}", prompt);
     io::stdout().flush().unwrap();
@@ -1,23 +1,35 @@
 use super::*;
-use todo_list::list::{task::Date, List};
+use todo_list::list::{
+    task::{Date, Priority, Recurrence},
+    view::Views,
+    List,
+};
 
 #[test]
 fn execute() {
     use ListError::*;
 
+    // Первая задача в пустом списке получает id 1
     let template_task = Task {
+        id: 1,
         title: "Test Task".into(),
-        descr: "This is a test task".into(),
-        category: "TestCategory".into(),
-        date: "2024-08-20 12:00".parse::<Date>().unwrap(),
-        is_done: false,
+        descr: Some("This is a test task".into()),
+        category: Some("TestCategory".into()),
+        tags: vec!["test".into(), "cli".into()],
+        date: Some("2024-08-20 12:00".parse::<Date>().unwrap()),
+        status: Status::Todo,
+        priority: Priority::High,
+        parent: 0,
+        depends_on: vec![],
+        repeat: Recurrence::default(),
     };
 
-    let mut list = List(vec![]);
+    let mut list = List::from(vec![]);
 
     let execute = |args, list: &mut List| {
         let matches = get_app().get_matches_from(args);
-        execute_command(matches.subcommand().unwrap(), list).map_err(|e| {
+        let mut views = Views::default();
+        execute_command(matches.subcommand().unwrap(), list, &mut views).map_err(|e| {
             let ExecuteError::ErrorsList(e) = e else {
                 unreachable!()
             };
@@ -33,6 +45,12 @@ fn execute() {
         "This is a test task",
         "2024-08-20 12:00",
         "TestCategory",
+        "--priority",
+        "high",
+        "--tag",
+        "test",
+        "-t",
+        "cli",
     ];
 
     assert_eq!(execute(add_args.clone(), &mut list), Ok(()));
@@ -45,6 +63,40 @@ fn execute() {
     assert_eq!(execute(add_args, &mut list), Err(TaskAlreadyExists));
     assert_eq!(list.len(), 1);
 
+    // tag
+    assert_eq!(
+        execute(vec!["todo_app", "tag", "add", "1", "x", "test"], &mut list),
+        Ok(())
+    );
+    assert_eq!(list[0].tags, vec!["test", "cli", "x"]);
+    assert_eq!(
+        execute(
+            vec!["todo_app", "tag", "remove", "Test Task", "x"],
+            &mut list
+        ),
+        Ok(())
+    );
+    assert_eq!(list[0], template_task);
+    assert_eq!(
+        execute(vec!["todo_app", "tag", "remove", "1", "x"], &mut list),
+        Err(TaskNotChanged)
+    );
+
+    // Статусы
+    assert_eq!(execute(vec!["todo_app", "start", "1"], &mut list), Ok(()));
+    assert_eq!(list[0].status, Status::InProgress);
+    assert_eq!(
+        execute(vec!["todo_app", "wait", "Test Task"], &mut list),
+        Ok(())
+    );
+    assert_eq!(list[0].status, Status::Waiting);
+    assert_eq!(execute(vec!["todo_app", "reopen", "1"], &mut list), Ok(()));
+    assert_eq!(list[0], template_task);
+    assert_eq!(
+        execute(vec!["todo_app", "reopen", "1"], &mut list),
+        Err(TaskAlreadyInStatus(Status::Todo))
+    );
+
     // done
     let done_args = vec!["todo_app", "done", "Test Task"];
 
@@ -53,22 +105,78 @@ fn execute() {
     assert_eq!(
         list[0],
         Task {
-            is_done: true,
+            status: Status::Done,
             ..template_task
         }
     );
 
     // Задача уже была заверешена -> Ошибка
     assert_eq!(execute(done_args, &mut list), Err(TaskAlreadyCompleted));
+    assert_eq!(
+        execute(vec!["todo_app", "cancel", "1"], &mut list),
+        Err(IllegalTransition(Status::Done, Status::Cancelled))
+    );
     // Задача не существует заверешена -> Ошибка
     assert_eq!(
         execute(vec!["todo_app", "done", "Do"], &mut list),
         Err(TaskNotExist("Do".into()))
     );
 
+    // Подзадача
+    assert_eq!(
+        execute(
+            vec![
+                "todo_app",
+                "add",
+                "Subtask",
+                "",
+                "2024-08-20 12:00",
+                "TestCategory",
+                "--parent",
+                "Test Task",
+                "--repeat",
+                "FREQ=DAILY;COUNT=2"
+            ],
+            &mut list
+        ),
+        Ok(())
+    );
+    assert_eq!(list[1].parent, 1);
+    assert_eq!(list[1].descr, None);
+
+    // depend
+    assert_eq!(
+        execute(
+            vec!["todo_app", "depend", "add", "Subtask", "Test Task"],
+            &mut list
+        ),
+        Ok(())
+    );
+    assert_eq!(list[1].depends_on, vec![1]);
+    assert_eq!(
+        execute(vec!["todo_app", "depend", "add", "1", "2"], &mut list),
+        Err(CyclicDependency(1))
+    );
+
+    // Повторяющаяся задача порождает следующую
+    assert_eq!(
+        execute(vec!["todo_app", "done", "Subtask"], &mut list),
+        Ok(())
+    );
+    assert_eq!(list.len(), 3);
+    assert!(list[1].repeat.is_none());
+    assert_eq!(
+        list[2].date,
+        Some("2024-08-21 12:00".parse::<Date>().unwrap())
+    );
+    assert_eq!(list[2].repeat.to_string(), "FREQ=DAILY;COUNT=1");
+    assert_eq!(execute(vec!["todo_app", "done", "3"], &mut list), Ok(()));
+    assert_eq!(list.len(), 3);
+
     // delete
     let delete_args = vec!["todo_app", "delete", "Test Task"];
 
+    // Вместе с подзадачей
     assert_eq!(execute(delete_args.clone(), &mut list), Ok(()));
     assert_eq!(list.len(), 0);
 
@@ -76,4 +184,72 @@ fn execute() {
         execute(delete_args.clone(), &mut list),
         Err(TaskNotExist("Test Task".into()))
     );
+
+    // Без срока
+    assert_eq!(execute(vec!["todo_app", "add", "Idea"], &mut list), Ok(()));
+    assert_eq!((&list[0].date, &list[0].category), (&None, &None));
+}
+
+#[test]
+fn view() {
+    use todo_list::list::SqlError::*;
+
+    let mut list = List::from(vec![Task {
+        title: "Test Task".into(),
+        date: Some("2024-08-20 12:00".parse::<Date>().unwrap()),
+        ..Task::default()
+    }]);
+    let mut views = Views::default();
+
+    let mut execute = |args: Vec<&str>, list: &mut List| {
+        let matches = get_app().get_matches_from(args);
+        execute_command(matches.subcommand().unwrap(), list, &mut views).map_err(|e| match e {
+            ExecuteError::ErrorsList(e) | ExecuteError::Query(_, e) => e,
+            ExecuteError::String(_) => unreachable!(),
+        })
+    };
+
+    let sql = "select title where is_done = false";
+    assert_eq!(
+        execute(vec!["todo_app", "view", "save", "open", sql], &mut list),
+        Ok(())
+    );
+    assert_eq!(
+        execute(vec!["todo_app", "view", "run", "open"], &mut list),
+        Ok(())
+    );
+    // Сломанный запрос не сохраняется
+    assert_eq!(
+        execute(
+            vec![
+                "todo_app",
+                "view",
+                "save",
+                "broken",
+                "select * where name = ''"
+            ],
+            &mut list
+        ),
+        Err(ListError::Sql(NonExistentField("name".into())))
+    );
+    assert_eq!(
+        execute(vec!["todo_app", "view", "run", "broken"], &mut list),
+        Err(ListError::ViewNotExist("broken".into()))
+    );
+    assert_eq!(
+        execute(vec!["todo_app", "view", "delete", "open"], &mut list),
+        Ok(())
+    );
+    assert_eq!(
+        execute(vec!["todo_app", "view", "delete", "open"], &mut list),
+        Err(ListError::ViewNotExist("open".into()))
+    );
+    assert_eq!(views, Views::default());
+
+    views.save("open", sql).unwrap();
+    assert_eq!(views.get("open"), Ok(sql));
+    assert_eq!(
+        views.run("open", &list).map(|rows| rows.rows),
+        Ok(vec![vec![serde_json::json!("Test Task")]])
+    );
 }